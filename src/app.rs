@@ -1,9 +1,42 @@
-use std::cmp::{max, min};
+use std::{
+    cmp::{max, min},
+    collections::HashSet,
+    fs,
+    path::PathBuf,
+};
 
-use ratatui::{layout::Rect, widgets::ListState};
-use tui_textarea::TextArea;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::Line,
+    widgets::ListState,
+};
+use regex::Regex;
+use reqwest::{
+    blocking::{Client, Request},
+    Method,
+};
+use tui_textarea::{CursorMove, TextArea};
 
-use crate::text_input::TextInput;
+use crate::{
+    auth::Auth,
+    auth_editor::AuthEditor,
+    collection::{self, CollectionEntry, KeyValue, SavedRequest},
+    curl,
+    environment::{self, Environment},
+    export::{self, ExportFormat},
+    file_picker::FilePicker,
+    graphql::{self, Completion, PendingSchema, SchemaState},
+    history::{self, HistoryEntry, HistoryResponse},
+    key_value_editor::{KeyValueEditor, TableKind},
+    oauth::{self, TokenState},
+    query,
+    request::{PendingRequest, PreparedRequest, RequestError, Response},
+    request_body::{self, BodyType, PreparedBody},
+    response,
+    search::Search,
+    text_input::TextInput,
+};
 
 pub struct StatefulList<T> {
     pub state: ListState,
@@ -55,13 +88,21 @@ impl<T> StatefulList<T> {
     }
 }
 
+/// Entry of the request type popup that asks for a method name.
+pub const CUSTOM_REQUEST_TYPE: &str = "Custom...";
+
 pub enum RequestType {
     Get,
     Post,
     Put,
+    Patch,
     Delete,
     Head,
     Options,
+    Trace,
+    Connect,
+    /// Any other method, like PURGE or REPORT.
+    Custom(String),
 }
 
 impl From<&str> for RequestType {
@@ -70,23 +111,30 @@ impl From<&str> for RequestType {
             "GET" => RequestType::Get,
             "POST" => RequestType::Post,
             "PUT" => RequestType::Put,
+            "PATCH" => RequestType::Patch,
             "DELETE" => RequestType::Delete,
             "HEAD" => RequestType::Head,
             "OPTIONS" => RequestType::Options,
-            _ => unreachable!("Unknown request type."),
+            "TRACE" => RequestType::Trace,
+            "CONNECT" => RequestType::Connect,
+            _ => RequestType::Custom(value.to_owned()),
         }
     }
 }
 
 impl RequestType {
-    pub fn as_str(&self) -> &'static str {
+    pub fn as_str(&self) -> &str {
         match self {
             RequestType::Get => "GET",
             RequestType::Post => "POST",
             RequestType::Put => "PUT",
+            RequestType::Patch => "PATCH",
             RequestType::Delete => "DELETE",
             RequestType::Head => "HEAD",
             RequestType::Options => "OPTIONS",
+            RequestType::Trace => "TRACE",
+            RequestType::Connect => "CONNECT",
+            RequestType::Custom(method) => method,
         }
     }
 }
@@ -98,27 +146,45 @@ pub enum SelectionState {
     Url,
     RequestTabs,
     RequestBody,
+    ResponseTabs,
+    ResponseFilter,
+    ResponseBody,
     Popup,
+    EnvironmentPopup,
+    Sidebar,
+    Prompt,
+    HistoryPopup,
+    ImportPopup,
+    ExportPopup,
+    FilePicker,
 }
 
 impl SelectionState {
     pub fn next(self) -> Self {
         match &self {
             SelectionState::None => SelectionState::RequestType,
+            SelectionState::Sidebar => SelectionState::RequestType,
             SelectionState::RequestType => SelectionState::Url,
             SelectionState::Url => SelectionState::RequestTabs,
             SelectionState::RequestTabs => SelectionState::RequestBody,
-            SelectionState::RequestBody => SelectionState::RequestType,
+            SelectionState::RequestBody => SelectionState::ResponseTabs,
+            SelectionState::ResponseTabs => SelectionState::ResponseFilter,
+            SelectionState::ResponseFilter => SelectionState::ResponseBody,
+            SelectionState::ResponseBody => SelectionState::Sidebar,
             _ => self,
         }
     }
 
     pub fn previous(self) -> Self {
         match &self {
+            SelectionState::ResponseBody => SelectionState::ResponseFilter,
+            SelectionState::ResponseFilter => SelectionState::ResponseTabs,
+            SelectionState::ResponseTabs => SelectionState::RequestBody,
             SelectionState::RequestBody => SelectionState::RequestTabs,
             SelectionState::RequestTabs => SelectionState::Url,
             SelectionState::Url => SelectionState::RequestType,
-            SelectionState::RequestType => SelectionState::RequestBody,
+            SelectionState::RequestType => SelectionState::Sidebar,
+            SelectionState::Sidebar => SelectionState::ResponseBody,
             _ => self,
         }
     }
@@ -141,22 +207,136 @@ impl<T> TabContainer<T> {
     }
 }
 
+pub enum ResponseState {
+    None,
+    Received(Box<Response>),
+    Failed(RequestError),
+    Cancelled,
+}
+
+/// What the single line prompt popup is asking for.
+pub enum PromptAction {
+    SaveAs,
+    Rename { path: PathBuf, is_folder: bool },
+    Delete { path: PathBuf, is_folder: bool },
+    CustomMethod,
+}
+
+pub struct Prompt<'a> {
+    pub action: PromptAction,
+    pub input: TextInput<'a>,
+}
+
+impl<'a> Prompt<'a> {
+    pub fn new(action: PromptAction) -> Self {
+        let mut input = TextInput::new("");
+        if let PromptAction::Rename { path, .. } = &action {
+            input.set_text(&path.to_string_lossy());
+        }
+        Self { action, input }
+    }
+
+    pub fn title(&self) -> String {
+        match &self.action {
+            PromptAction::SaveAs => "Save request as (use / for folders)".to_owned(),
+            PromptAction::Rename { .. } => "Rename to".to_owned(),
+            PromptAction::Delete { path, .. } => format!("Delete '{}'? (y/n)", path.display()),
+            PromptAction::CustomMethod => "Request method".to_owned(),
+        }
+    }
+}
+
 pub struct AppWidgets<'a> {
     pub url_text_input: TextInput<'a>,
-    pub query_params_text_area: TextArea<'a>,
-    pub headers_text_area: TextArea<'a>,
+    pub query_editor: KeyValueEditor<'a>,
+    pub headers_editor: KeyValueEditor<'a>,
     pub body_text_area: TextArea<'a>,
-    pub auth_text_area: TextArea<'a>,
+    pub form_editor: KeyValueEditor<'a>,
+    pub multipart_editor: KeyValueEditor<'a>,
+    /// Path of the file sent as a binary body.
+    pub body_file_input: TextInput<'a>,
+    pub graphql_query_area: TextArea<'a>,
+    /// The variables of the GraphQL query, as a JSON object.
+    pub graphql_variables_area: TextArea<'a>,
+    pub auth_editor: AuthEditor<'a>,
 }
 
 pub struct App<'a> {
     pub widgets: AppWidgets<'a>,
     pub selected_request_type: RequestType,
+    pub body_type: BodyType,
+    /// Why the JSON body or the GraphQL variables do not parse, shown under
+    /// the Body tab.
+    pub body_error: Option<serde_json::Error>,
+    /// Size of the file sent as a binary body, `None` if the path is not a
+    /// file. Looked up when the path changes rather than on every frame.
+    pub body_file_size: Option<u64>,
+    /// Keys go to the GraphQL variables instead of the query.
+    pub graphql_variables_focused: bool,
+    /// The schema of the GraphQL endpoint, for completing field names.
+    pub graphql_schema: SchemaState,
     pub selected_input: SelectionState,
     pub request_types: StatefulList<&'a str>,
     pub tabs: TabContainer<&'a str>,
+    pub response_tabs: TabContainer<&'a str>,
     pub show_request_type_popup: bool,
-    pub response_text: String,
+    pub environments: Vec<Environment>,
+    pub selected_environment: Option<usize>,
+    /// "No environment" followed by the environment names.
+    pub environment_list: StatefulList<String>,
+    pub show_environment_popup: bool,
+    /// Set when the environments file could not be read.
+    pub environment_error: Option<String>,
+    /// Selection to return to once the environment popup is closed.
+    pub selection_before_popup: SelectionState,
+    /// Pattern currently highlighting unresolved `{{name}}` placeholders.
+    pub unresolved_pattern: Option<Regex>,
+    pub collection_entries: Vec<CollectionEntry>,
+    pub collection_state: ListState,
+    pub collapsed_folders: HashSet<PathBuf>,
+    /// The saved request currently loaded in the editor.
+    pub open_request: Option<PathBuf>,
+    /// Result of the last collection action, shown under the sidebar.
+    pub collection_message: Option<String>,
+    pub prompt: Option<Prompt<'a>>,
+    /// Sent requests, newest first.
+    pub history: Vec<HistoryEntry>,
+    pub show_history_popup: bool,
+    pub history_filter: TextInput<'a>,
+    pub history_state: ListState,
+    /// Set when the history file could not be read or written.
+    pub history_error: Option<String>,
+    pub show_import_popup: bool,
+    /// The pasted curl command.
+    pub import_text_area: TextArea<'a>,
+    /// Why the pasted command could not be imported.
+    pub import_error: Option<String>,
+    pub show_export_popup: bool,
+    pub export_formats: TabContainer<ExportFormat>,
+    /// The request being exported with the body it was built from, or why it
+    /// could not be built.
+    pub export_request: Result<(Request, Option<PreparedBody>), String>,
+    /// File name being entered to write the export to.
+    pub export_file: Option<TextInput<'a>>,
+    /// Result of the last copy or write.
+    pub export_message: Option<String>,
+    /// Browses for the file of a binary body.
+    pub file_picker: Option<FilePicker>,
+    pub response: ResponseState,
+    pub pretty_response: bool,
+    /// The response body as rendered in the Body tab.
+    pub response_lines: Vec<Line<'static>>,
+    /// Index of the first response line shown in the Body tab.
+    pub response_scroll: usize,
+    /// Where the response content was last drawn, used for mouse wheel events.
+    pub response_area: Rect,
+    pub search: Search<'a>,
+    /// jq-like expression narrowing down JSON responses.
+    pub response_filter: TextInput<'a>,
+    /// Why the filter could not be applied. The last good result stays on screen.
+    pub response_filter_error: Option<String>,
+    pub pending_request: Option<PendingRequest>,
+    pub tick_count: usize,
     pub last_mouse_down_event: Option<(u16, u16)>,
 }
 
@@ -167,26 +347,901 @@ impl<'a> App<'a> {
             widgets: {
                 AppWidgets {
                     url_text_input: TextInput::new("Url"),
-                    query_params_text_area: TextArea::default(),
-                    headers_text_area: TextArea::default(),
+                    query_editor: KeyValueEditor::new(TableKind::Query),
+                    headers_editor: KeyValueEditor::new(TableKind::Headers),
                     body_text_area: TextArea::default(),
-                    auth_text_area: TextArea::default(),
+                    form_editor: KeyValueEditor::new(TableKind::Form),
+                    multipart_editor: KeyValueEditor::new(TableKind::Multipart),
+                    body_file_input: TextInput::new("File"),
+                    graphql_query_area: TextArea::default(),
+                    graphql_variables_area: TextArea::default(),
+                    auth_editor: AuthEditor::new(),
                 }
             },
             selected_request_type,
+            body_type: BodyType::None,
+            body_error: None,
+            body_file_size: None,
+            graphql_variables_focused: false,
+            graphql_schema: SchemaState::None,
             selected_input: SelectionState::None,
             request_types: StatefulList::with_items(vec![
-                "GET", "POST", "PUT", "DELETE", "HEAD", "OPTIONS",
+                "GET",
+                "POST",
+                "PUT",
+                "PATCH",
+                "DELETE",
+                "HEAD",
+                "OPTIONS",
+                "TRACE",
+                "CONNECT",
+                CUSTOM_REQUEST_TYPE,
             ]),
             tabs: TabContainer {
                 items: vec!["Query", "Headers", "Body", "Auth"],
                 index: 0,
             },
+            response_tabs: TabContainer {
+                items: vec!["Body", "Headers", "Cookies"],
+                index: 0,
+            },
             show_request_type_popup: false,
-            response_text: String::new(),
+            environments: Vec::new(),
+            selected_environment: None,
+            environment_list: StatefulList::with_items(Vec::new()),
+            show_environment_popup: false,
+            environment_error: None,
+            selection_before_popup: SelectionState::None,
+            unresolved_pattern: None,
+            collection_entries: Vec::new(),
+            collection_state: ListState::default(),
+            collapsed_folders: HashSet::new(),
+            open_request: None,
+            collection_message: None,
+            prompt: None,
+            history: Vec::new(),
+            show_history_popup: false,
+            history_filter: TextInput::new("Filter"),
+            history_state: ListState::default(),
+            history_error: None,
+            show_import_popup: false,
+            import_text_area: TextArea::default(),
+            import_error: None,
+            show_export_popup: false,
+            export_formats: TabContainer {
+                items: ExportFormat::ALL.to_vec(),
+                index: 0,
+            },
+            export_request: Err(String::new()),
+            export_file: None,
+            export_message: None,
+            file_picker: None,
+            response: ResponseState::None,
+            pretty_response: true,
+            response_lines: Vec::new(),
+            response_scroll: 0,
+            response_area: Rect::default(),
+            search: Search::new(),
+            response_filter: TextInput::new("Filter"),
+            response_filter_error: None,
+            pending_request: None,
+            tick_count: 0,
             last_mouse_down_event: None,
         }
     }
+
+    pub fn active_environment(&self) -> Option<&Environment> {
+        self.environments.get(self.selected_environment?)
+    }
+
+    /// Reloads the environments file so edits made outside restui show up, and
+    /// opens the environment picker.
+    pub fn open_environment_popup(&mut self) {
+        let selected_name = self
+            .active_environment()
+            .map(|environment| environment.name.clone());
+
+        match environment::load_environments() {
+            Ok(environments) => {
+                self.environments = environments;
+                self.environment_error = None;
+            }
+            Err(err) => self.environment_error = Some(err),
+        }
+        self.selected_environment = selected_name.and_then(|name| {
+            self.environments
+                .iter()
+                .position(|environment| environment.name == name)
+        });
+
+        let mut items = vec!["No environment".to_owned()];
+        items.extend(
+            self.environments
+                .iter()
+                .map(|environment| environment.name.clone()),
+        );
+        self.environment_list = StatefulList::with_items(items);
+        self.environment_list
+            .state
+            .select(Some(self.selected_environment.map_or(0, |index| index + 1)));
+
+        self.show_environment_popup = true;
+        if !matches!(self.selected_input, SelectionState::EnvironmentPopup) {
+            self.selection_before_popup = self.selected_input;
+        }
+        self.selected_input = SelectionState::EnvironmentPopup;
+    }
+
+    pub fn close_environment_popup(&mut self) {
+        self.show_environment_popup = false;
+        self.selected_input = self.selection_before_popup;
+    }
+
+    /// Activates the environment highlighted in the picker.
+    pub fn select_environment(&mut self) {
+        if let Some(index) = self.environment_list.state.selected() {
+            self.selected_environment = index.checked_sub(1);
+        }
+        self.forget_stale_graphql_schema();
+        self.close_environment_popup();
+    }
+
+    /// Highlights `{{name}}` placeholders the active environment cannot resolve.
+    pub fn refresh_variable_highlights(&mut self) {
+        let widgets = &self.widgets;
+        let texts = std::iter::once(widgets.url_text_input.text())
+            .chain(widgets.query_editor.texts())
+            .chain(widgets.headers_editor.texts())
+            .chain(widgets.body_text_area.lines().iter().map(String::as_str))
+            .chain(widgets.form_editor.texts())
+            .chain(widgets.multipart_editor.texts())
+            .chain(std::iter::once(widgets.body_file_input.text()))
+            .chain(
+                widgets
+                    .graphql_query_area
+                    .lines()
+                    .iter()
+                    .map(String::as_str),
+            )
+            .chain(
+                widgets
+                    .graphql_variables_area
+                    .lines()
+                    .iter()
+                    .map(String::as_str),
+            )
+            .chain(widgets.auth_editor.texts());
+        let pattern = environment::unresolved_pattern(texts, self.active_environment());
+
+        if pattern.as_deref() == self.unresolved_pattern.as_ref().map(Regex::as_str) {
+            return;
+        }
+
+        self.unresolved_pattern = pattern
+            .as_deref()
+            .and_then(|pattern| Regex::new(pattern).ok());
+        let widgets = &mut self.widgets;
+        for text_area in [
+            &mut widgets.body_text_area,
+            &mut widgets.graphql_query_area,
+            &mut widgets.graphql_variables_area,
+        ] {
+            let _ = text_area.set_search_pattern(pattern.as_deref().unwrap_or_default());
+            text_area.set_search_style(Style::default().fg(Color::White).bg(Color::Red));
+        }
+    }
+
+    /// State of the OAuth 2.0 token for the Auth tab's settings, with variables
+    /// resolved the way the request would resolve them.
+    pub fn oauth_token_state(&self) -> Option<TokenState> {
+        let environment = self.active_environment();
+        let mut unresolved = Vec::new();
+        match self
+            .widgets
+            .auth_editor
+            .to_auth()
+            .map_text(|text| environment::substitute(text, environment, &mut unresolved))
+        {
+            Auth::OAuth2(config) => Some(oauth::token_state(&config)),
+            _ => None,
+        }
+    }
+
+    /// Rewrites the Query tab from the query string in the URL bar.
+    pub fn sync_query_from_url(&mut self) {
+        let (_, url_query, _) = query::split_url(self.widgets.url_text_input.text());
+        let pairs = url_query.map(query::parse).unwrap_or_default();
+        self.widgets.query_editor.set_enabled_pairs(pairs);
+    }
+
+    /// Rewrites the query string in the URL bar from the Query tab.
+    pub fn sync_url_from_query(&mut self) {
+        let url = self.widgets.url_text_input.text();
+        let synced = query::with_query(url, self.widgets.query_editor.enabled_pairs());
+        if synced != url {
+            self.widgets.url_text_input.set_text(&synced);
+        }
+    }
+
+    /// Validates the body again after it or its type changed.
+    pub fn refresh_body_error(&mut self) {
+        self.body_error = match self.body_type {
+            BodyType::Json => {
+                request_body::json_error(&self.widgets.body_text_area.lines().join("\n"))
+            }
+            BodyType::GraphQl => {
+                request_body::json_error(&self.widgets.graphql_variables_area.lines().join("\n"))
+            }
+            _ => None,
+        };
+    }
+
+    /// Looks up the file of the binary body again after its path changed.
+    pub fn refresh_body_file(&mut self) {
+        self.body_file_size = fs::metadata(self.widgets.body_file_input.text().trim())
+            .ok()
+            .filter(|metadata| metadata.is_file())
+            .map(|metadata| metadata.len());
+    }
+
+    /// Byte offset of the cursor in the GraphQL query.
+    pub fn graphql_cursor(&self) -> usize {
+        let query_area = &self.widgets.graphql_query_area;
+        let (row, column) = query_area.cursor();
+        let lines = query_area.lines();
+        let before: usize = lines[..row].iter().map(|line| line.len() + 1).sum();
+        let line = &lines[row];
+        before
+            + line
+                .char_indices()
+                .nth(column)
+                .map_or(line.len(), |(index, _)| index)
+    }
+
+    /// Fields of the loaded schema that fit where the cursor is in the query.
+    pub fn graphql_completion(&self) -> Option<Completion<'_>> {
+        let SchemaState::Loaded(schema) = &self.graphql_schema else {
+            return None;
+        };
+        let query = self.widgets.graphql_query_area.lines().join("\n");
+        graphql::complete(schema, &query, self.graphql_cursor())
+    }
+
+    /// Extends the field name in front of the cursor as far as the schema
+    /// allows. Returns false when there is nothing to complete, so the key can
+    /// be typed instead.
+    pub fn complete_graphql_field(&mut self) -> bool {
+        let suffix = match self.graphql_completion() {
+            Some(completion) if !completion.prefix.is_empty() && !completion.fields.is_empty() => {
+                completion.common_suffix().to_owned()
+            }
+            _ => return false,
+        };
+        self.widgets.graphql_query_area.insert_str(suffix);
+        true
+    }
+
+    /// Sends the introspection query to the request's URL in the background.
+    pub fn fetch_graphql_schema(&mut self) {
+        self.graphql_schema = match PreparedRequest::introspection(self) {
+            Ok(request) => SchemaState::Loading(PendingSchema::spawn(request)),
+            Err(err) => SchemaState::Failed(err.to_string()),
+        };
+    }
+
+    /// Takes the schema once the introspection query has finished.
+    pub fn poll_graphql_schema(&mut self) {
+        let SchemaState::Loading(pending_schema) = &self.graphql_schema else {
+            return;
+        };
+        if let Some(result) = pending_schema.poll() {
+            self.graphql_schema = match result {
+                Ok(schema) => SchemaState::Loaded(schema),
+                Err(err) => SchemaState::Failed(err),
+            };
+            // The URL may have changed while it was loading.
+            self.forget_stale_graphql_schema();
+        }
+    }
+
+    /// Drops the schema once the request's URL, as it would be sent, is no
+    /// longer the one it was fetched from, so another endpoint's fields are
+    /// not offered.
+    pub fn forget_stale_graphql_schema(&mut self) {
+        let SchemaState::Loaded(schema) = &self.graphql_schema else {
+            return;
+        };
+        let url = environment::substitute(
+            &query::without_query(self.widgets.url_text_input.text()),
+            self.active_environment(),
+            &mut Vec::new(),
+        );
+        if url != schema.url {
+            self.graphql_schema = SchemaState::None;
+        }
+    }
+
+    /// Pretty-prints the JSON body. It stays as it is when it does not parse,
+    /// the error is on screen already.
+    pub fn format_json_body(&mut self) {
+        let body_text_area = &mut self.widgets.body_text_area;
+        if let Ok(pretty) = request_body::format_json(&body_text_area.lines().join("\n")) {
+            // Replaced as an edit, so it can be undone.
+            body_text_area.select_all();
+            body_text_area.insert_str(pretty);
+            body_text_area.move_cursor(CursorMove::Top);
+            self.refresh_body_error();
+        }
+    }
+
+    /// Opens the file picker where the file of the binary body is.
+    pub fn open_file_picker(&mut self) {
+        self.file_picker = Some(FilePicker::open(self.widgets.body_file_input.text()));
+        if !matches!(self.selected_input, SelectionState::FilePicker) {
+            self.selection_before_popup = self.selected_input;
+        }
+        self.selected_input = SelectionState::FilePicker;
+    }
+
+    pub fn close_file_picker(&mut self) {
+        self.file_picker = None;
+        self.selected_input = self.selection_before_popup;
+    }
+
+    /// Opens the selected folder, or takes the selected file as the body.
+    pub fn choose_file(&mut self) {
+        let Some(picker) = &mut self.file_picker else {
+            return;
+        };
+        if let Some(path) = picker.choose() {
+            let path = path.to_string_lossy().into_owned();
+            self.widgets.body_file_input.set_text(&path);
+            self.refresh_body_file();
+            self.close_file_picker();
+        }
+    }
+
+    /// Copies the editor widgets into a request that can be written to disk.
+    pub fn saved_request(&self) -> SavedRequest {
+        SavedRequest {
+            method: self.selected_request_type.as_str().to_owned(),
+            // The query string is kept in the Query tab.
+            url: query::without_query(self.widgets.url_text_input.text()),
+            query: self.widgets.query_editor.to_rows(),
+            headers: self.widgets.headers_editor.to_rows(),
+            body_type: self.body_type,
+            body: self.widgets.body_text_area.lines().join("\n"),
+            form: self.widgets.form_editor.to_rows(),
+            multipart: self.widgets.multipart_editor.to_rows(),
+            body_file: self.widgets.body_file_input.text().to_owned(),
+            graphql_query: self.widgets.graphql_query_area.lines().join("\n"),
+            graphql_variables: self.widgets.graphql_variables_area.lines().join("\n"),
+            auth: self.widgets.auth_editor.to_auth(),
+        }
+    }
+
+    /// Replaces the editor widgets with a saved request.
+    pub fn load_saved_request(&mut self, request: &SavedRequest) -> Result<(), String> {
+        let method = request.method.trim();
+        if Method::from_bytes(method.as_bytes()).is_err() {
+            return Err(format!("unsupported request type '{}'", request.method));
+        }
+        self.selected_request_type = RequestType::from(method);
+
+        let lines = |text: &str| text.lines().map(String::from).collect();
+        // A query string in the saved URL is sent along with the Query tab.
+        let (_, url_query, _) = query::split_url(&request.url);
+        let query_rows: Vec<KeyValue> = url_query
+            .map(query::parse)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(key, value)| KeyValue::new(&key, &value))
+            .chain(request.query.iter().cloned())
+            .collect();
+        self.widgets.url_text_input.set_text(&request.url);
+        self.widgets.query_editor = KeyValueEditor::from_rows(&query_rows, TableKind::Query);
+        self.sync_url_from_query();
+        self.widgets.headers_editor =
+            KeyValueEditor::from_rows(&request.headers, TableKind::Headers);
+        self.body_type = request.body_type;
+        self.widgets.body_text_area = TextArea::new(lines(&request.body));
+        self.widgets.graphql_query_area = TextArea::new(lines(&request.graphql_query));
+        self.widgets.graphql_variables_area = TextArea::new(lines(&request.graphql_variables));
+        self.graphql_schema = SchemaState::None;
+        self.refresh_body_error();
+        self.widgets.form_editor = KeyValueEditor::from_rows(&request.form, TableKind::Form);
+        self.widgets.multipart_editor =
+            KeyValueEditor::from_rows(&request.multipart, TableKind::Multipart);
+        self.widgets.body_file_input.set_text(&request.body_file);
+        self.refresh_body_file();
+        self.widgets.auth_editor = AuthEditor::from_auth(&request.auth);
+
+        // The new body text areas have no highlighting yet, force it to be set again.
+        self.unresolved_pattern = None;
+        Ok(())
+    }
+
+    /// Reads the collections directory again, keeping the selection in range.
+    pub fn refresh_collection(&mut self) {
+        match collection::load_tree(&self.collapsed_folders) {
+            Ok(entries) => self.collection_entries = entries,
+            Err(err) => self.collection_message = Some(err.to_string()),
+        }
+
+        let selected = match self.collection_state.selected() {
+            _ if self.collection_entries.is_empty() => None,
+            Some(index) => Some(min(index, self.collection_entries.len() - 1)),
+            None => Some(0),
+        };
+        self.collection_state.select(selected);
+    }
+
+    /// Selects the entry at `path`, if it is listed.
+    pub fn select_collection_entry(&mut self, path: &PathBuf) {
+        if let Some(index) = self
+            .collection_entries
+            .iter()
+            .position(|entry| &entry.path == path)
+        {
+            self.collection_state.select(Some(index));
+        }
+    }
+
+    pub fn select_next_collection_entry(&mut self) {
+        if let Some(index) = self.collection_state.selected() {
+            let last = self.collection_entries.len().saturating_sub(1);
+            self.collection_state.select(Some(min(index + 1, last)));
+        }
+    }
+
+    pub fn select_previous_collection_entry(&mut self) {
+        if let Some(index) = self.collection_state.selected() {
+            self.collection_state.select(Some(index.saturating_sub(1)));
+        }
+    }
+
+    pub fn selected_collection_entry(&self) -> Option<&CollectionEntry> {
+        self.collection_entries
+            .get(self.collection_state.selected()?)
+    }
+
+    /// Opens the selected request in the editor, or expands/collapses the
+    /// selected folder.
+    pub fn open_selected_entry(&mut self) {
+        let Some(entry) = self.selected_collection_entry() else {
+            return;
+        };
+        let path = entry.path.clone();
+
+        if entry.is_folder {
+            if !self.collapsed_folders.remove(&path) {
+                self.collapsed_folders.insert(path);
+            }
+            self.refresh_collection();
+            return;
+        }
+
+        let result =
+            collection::load_request(&path).and_then(|request| self.load_saved_request(&request));
+        match result {
+            Ok(()) => {
+                self.collection_message = Some(format!("Opened {}", path.display()));
+                self.open_request = Some(path);
+            }
+            Err(err) => self.collection_message = Some(err),
+        }
+    }
+
+    /// Saves the editor over the open request, or asks for a name if the
+    /// request has never been saved.
+    pub fn save_request(&mut self) {
+        match self.open_request.clone() {
+            Some(path) => {
+                let result = collection::save_request(&path, &self.saved_request());
+                self.collection_message = Some(match result {
+                    Ok(()) => format!("Saved {}", path.display()),
+                    Err(err) => err,
+                });
+                self.refresh_collection();
+            }
+            None => self.open_prompt(PromptAction::SaveAs),
+        }
+    }
+
+    pub fn open_prompt(&mut self, action: PromptAction) {
+        self.prompt = Some(Prompt::new(action));
+        if !matches!(self.selected_input, SelectionState::Prompt) {
+            self.selection_before_popup = self.selected_input;
+        }
+        self.selected_input = SelectionState::Prompt;
+    }
+
+    pub fn close_prompt(&mut self) {
+        self.prompt = None;
+        self.selected_input = self.selection_before_popup;
+    }
+
+    /// Carries out the prompt's action with the entered text.
+    pub fn confirm_prompt(&mut self) {
+        if let Some(Prompt {
+            action: PromptAction::CustomMethod,
+            input,
+        }) = &self.prompt
+        {
+            // Stay open until the name is a valid method token.
+            let method = input.text().trim();
+            if Method::from_bytes(method.as_bytes()).is_ok() {
+                self.selected_request_type = RequestType::from(method);
+                self.close_prompt();
+            }
+            return;
+        }
+
+        let Some(prompt) = self.prompt.take() else {
+            return;
+        };
+        self.close_prompt();
+
+        let result = match prompt.action {
+            PromptAction::SaveAs => {
+                collection::parse_request_path(prompt.input.text()).and_then(|path| {
+                    collection::save_request(&path, &self.saved_request())?;
+                    self.open_request = Some(path.clone());
+                    Ok(format!("Saved {}", path.display()))
+                })
+            }
+            PromptAction::Rename { path, is_folder } => {
+                collection::parse_request_path(prompt.input.text()).and_then(|new_path| {
+                    collection::rename_entry(&path, &new_path, is_folder)?;
+                    if self.open_request.as_ref() == Some(&path) {
+                        self.open_request = Some(new_path.clone());
+                    }
+                    Ok(format!("Renamed to {}", new_path.display()))
+                })
+            }
+            PromptAction::Delete { path, is_folder } => collection::delete_entry(&path, is_folder)
+                .map(|()| {
+                    if self.open_request.as_ref() == Some(&path) {
+                        self.open_request = None;
+                    }
+                    format!("Deleted {}", path.display())
+                }),
+            PromptAction::CustomMethod => return,
+        };
+
+        self.collection_message = Some(result.unwrap_or_else(|err| err));
+        self.refresh_collection();
+        if let Some(path) = self.open_request.clone() {
+            self.select_collection_entry(&path);
+        }
+    }
+
+    /// Moves the result of a finished request into the response pane.
+    pub fn poll_pending_request(&mut self) {
+        let Some(result) = self.pending_request.as_ref().and_then(PendingRequest::poll) else {
+            return;
+        };
+
+        if let Some(pending_request) = self.pending_request.take() {
+            self.record_history(pending_request, &result);
+        }
+        self.response = match result {
+            Ok(response) => ResponseState::Received(Box::new(response)),
+            Err(err) => ResponseState::Failed(err),
+        };
+        self.response_scroll = 0;
+        self.response_lines.clear();
+        self.refresh_response_view();
+    }
+
+    pub fn refresh_response_view(&mut self) {
+        self.response_filter_error = None;
+        match &self.response {
+            ResponseState::Received(response) => {
+                let filter = self.response_filter.text();
+                match response::body_lines(response, self.pretty_response, filter) {
+                    Ok(lines) => self.response_lines = lines,
+                    Err(err) => {
+                        self.response_filter_error = Some(err);
+                        // Nothing to keep on screen for a fresh response, show it unfiltered.
+                        if self.response_lines.is_empty() {
+                            self.response_lines =
+                                response::body_lines(response, self.pretty_response, "")
+                                    .unwrap_or_default();
+                        }
+                    }
+                }
+            }
+            _ => self.response_lines.clear(),
+        }
+        self.scroll_response_to(self.response_scroll);
+        self.update_search();
+    }
+
+    /// Searches the response lines again after the pattern, the options or the
+    /// response changed.
+    pub fn update_search(&mut self) {
+        self.search
+            .update(&self.response_lines, self.response_scroll);
+        self.reveal_current_match();
+    }
+
+    /// Scrolls the response so the current search match is on screen.
+    pub fn reveal_current_match(&mut self) {
+        let Some(current_match) = self.search.current_match() else {
+            return;
+        };
+
+        let page_height = self.response_page_height();
+        if current_match.line < self.response_scroll
+            || current_match.line >= self.response_scroll + page_height
+        {
+            self.scroll_response_to(current_match.line.saturating_sub(page_height / 3));
+        }
+    }
+
+    /// Number of response lines that fit in the response pane.
+    pub fn response_page_height(&self) -> usize {
+        self.response_area.height.saturating_sub(2) as usize
+    }
+
+    pub fn scroll_response_to(&mut self, line: usize) {
+        let max_scroll = self
+            .response_lines
+            .len()
+            .saturating_sub(self.response_page_height());
+        self.response_scroll = min(line, max_scroll);
+    }
+
+    pub fn scroll_response_up(&mut self, lines: usize) {
+        self.scroll_response_to(self.response_scroll.saturating_sub(lines));
+    }
+
+    pub fn scroll_response_down(&mut self, lines: usize) {
+        self.scroll_response_to(self.response_scroll.saturating_add(lines));
+    }
+
+    /// Cancels the in-flight request. Its worker stops sending or receiving
+    /// and whatever it reports back is discarded.
+    pub fn cancel_pending_request(&mut self) {
+        if let Some(pending_request) = self.pending_request.take() {
+            pending_request.cancel();
+            self.record_history(
+                pending_request,
+                &Err(RequestError::Other("Request cancelled".to_owned())),
+            );
+            self.response = ResponseState::Cancelled;
+        }
+    }
+
+    /// Appends a finished request to the history, in memory and on disk.
+    fn record_history(
+        &mut self,
+        pending_request: PendingRequest,
+        result: &Result<Response, RequestError>,
+    ) {
+        let entry = HistoryEntry {
+            timestamp: chrono::Utc::now(),
+            method: pending_request.method,
+            url: pending_request.url,
+            status: result
+                .as_ref()
+                .ok()
+                .map(|response| response.status.as_u16()),
+            // Without the wait for the next tick that picked the result up.
+            duration_ms: match result {
+                Ok(response) => response.elapsed,
+                Err(_) => pending_request.started.elapsed(),
+            }
+            .as_millis() as u64,
+            error: result.as_ref().err().map(ToString::to_string),
+            request: SavedRequest {
+                auth: pending_request.saved_request.auth.without_secrets(),
+                ..pending_request.saved_request
+            },
+            response: result.as_ref().ok().map(HistoryResponse::from),
+        };
+
+        if let Err(err) = history::append_history(&entry) {
+            self.history_error = Some(err);
+        }
+        self.history.insert(0, entry);
+    }
+
+    /// Loads the history file and opens the history popup.
+    pub fn open_history_popup(&mut self) {
+        match history::load_history() {
+            Ok(history) => {
+                self.history = history;
+                self.history_error = None;
+            }
+            Err(err) => self.history_error = Some(err),
+        }
+        self.history_filter.clear();
+        self.history_state.select(Some(0));
+
+        self.show_history_popup = true;
+        if !matches!(self.selected_input, SelectionState::HistoryPopup) {
+            self.selection_before_popup = self.selected_input;
+        }
+        self.selected_input = SelectionState::HistoryPopup;
+    }
+
+    pub fn close_history_popup(&mut self) {
+        self.show_history_popup = false;
+        self.selected_input = self.selection_before_popup;
+    }
+
+    /// History entries matching the filter.
+    pub fn filtered_history(&self) -> Vec<&HistoryEntry> {
+        let filter = self.history_filter.text();
+        self.history
+            .iter()
+            .filter(|entry| entry.matches(filter))
+            .collect()
+    }
+
+    pub fn select_next_history_entry(&mut self) {
+        let last = self.filtered_history().len().saturating_sub(1);
+        let index = self.history_state.selected().map_or(0, |index| index + 1);
+        self.history_state.select(Some(min(index, last)));
+    }
+
+    pub fn select_previous_history_entry(&mut self) {
+        let index = self.history_state.selected().unwrap_or(0);
+        self.history_state.select(Some(index.saturating_sub(1)));
+    }
+
+    /// Puts the selected history entry back into the editor and shows its
+    /// response.
+    pub fn restore_history_entry(&mut self) {
+        let Some(entry) = self
+            .history_state
+            .selected()
+            .and_then(|index| self.filtered_history().get(index).copied())
+        else {
+            return;
+        };
+
+        let request = entry.request.clone();
+        let response = match (&entry.response, &entry.error) {
+            (Some(response), _) => {
+                ResponseState::Received(Box::new(response.to_response(entry.duration_ms)))
+            }
+            (None, Some(err)) => ResponseState::Failed(RequestError::Other(err.clone())),
+            (None, None) => ResponseState::None,
+        };
+
+        if let Err(err) = self.load_saved_request(&request) {
+            self.history_error = Some(err);
+            return;
+        }
+        self.open_request = None;
+        self.response = response;
+        self.response_scroll = 0;
+        self.response_lines.clear();
+        self.refresh_response_view();
+        self.close_history_popup();
+    }
+
+    pub fn open_import_popup(&mut self) {
+        self.import_text_area = TextArea::default();
+        self.import_error = None;
+        self.show_import_popup = true;
+        if !matches!(self.selected_input, SelectionState::ImportPopup) {
+            self.selection_before_popup = self.selected_input;
+        }
+        self.selected_input = SelectionState::ImportPopup;
+    }
+
+    pub fn close_import_popup(&mut self) {
+        self.show_import_popup = false;
+        self.selected_input = self.selection_before_popup;
+    }
+
+    /// Imports the pasted curl command into the editor. A line ending in a
+    /// backslash continues on the next line, and so does a quoted argument
+    /// spanning several lines, so pasting a multi-line command only imports
+    /// it once the last line is in.
+    pub fn confirm_import(&mut self) {
+        let (row, _) = self.import_text_area.cursor();
+        let command = self.import_text_area.lines().join("\n");
+        if self.import_text_area.lines()[row].ends_with('\\') || curl::is_unterminated(&command) {
+            self.import_text_area.insert_newline();
+            return;
+        }
+
+        let result = curl::parse(&command).and_then(|(request, warnings)| {
+            self.load_saved_request(&request)?;
+            Ok(warnings)
+        });
+        match result {
+            Ok(warnings) => {
+                if !warnings.is_empty() {
+                    self.collection_message = Some(format!("Imported, {}", warnings.join(", ")));
+                }
+            }
+            Err(err) => {
+                self.import_error = Some(err);
+                return;
+            }
+        }
+        self.open_request = None;
+        self.close_import_popup();
+    }
+
+    /// Builds the request the way it would be sent and opens the export popup.
+    pub fn open_export_popup(&mut self) {
+        self.export_request = PreparedRequest::from_app(self)
+            .and_then(|request| {
+                // Multipart forms and files are streamed, the built request has
+                // no bytes to export for them.
+                let body = request.body.clone();
+                Ok((request.build(&Client::new())?, body))
+            })
+            .map_err(|err| err.to_string());
+        self.export_file = None;
+        self.export_message = None;
+        self.show_export_popup = true;
+        if !matches!(self.selected_input, SelectionState::ExportPopup) {
+            self.selection_before_popup = self.selected_input;
+        }
+        self.selected_input = SelectionState::ExportPopup;
+    }
+
+    pub fn close_export_popup(&mut self) {
+        self.show_export_popup = false;
+        self.selected_input = self.selection_before_popup;
+    }
+
+    pub fn export_format(&self) -> ExportFormat {
+        self.export_formats.items[self.export_formats.index]
+    }
+
+    /// The request in the selected export format.
+    pub fn export_text(&self) -> Result<String, &str> {
+        match &self.export_request {
+            Ok((request, body)) => Ok(self.export_format().render(request, body.as_ref())),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn copy_export(&mut self) {
+        let Ok(text) = self.export_text() else {
+            return;
+        };
+        self.export_message = Some(match export::copy_to_clipboard(&text) {
+            Ok(()) => "Copied to clipboard".to_owned(),
+            Err(err) => format!("Could not copy: {err}"),
+        });
+    }
+
+    /// Asks for the file to write the export to, named after the open request.
+    pub fn start_export_file(&mut self) {
+        if self.export_request.is_err() {
+            return;
+        }
+        let name = self
+            .open_request
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "request".to_owned());
+        let mut input = TextInput::new("Write to file");
+        input.set_text(&format!("{name}.{}", self.export_format().file_extension()));
+        self.export_file = Some(input);
+    }
+
+    pub fn write_export_file(&mut self) {
+        let (Some(input), Ok(text)) = (self.export_file.take(), self.export_text()) else {
+            return;
+        };
+        let path = input.text();
+        self.export_message = Some(match fs::write(path, text + "\n") {
+            Ok(()) => format!("Wrote {path}"),
+            Err(err) => format!("{path}: {err}"),
+        });
+    }
 }
 
 pub fn is_within_block(block: &Rect, position: (u16, u16)) -> bool {
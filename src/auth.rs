@@ -0,0 +1,235 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use reqwest::{
+    blocking::Request,
+    header::{HeaderName, HeaderValue, AUTHORIZATION},
+};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{
+    environment::placeholder_regex,
+    hmac_auth::{self, HmacConfig},
+    oauth::{self, OAuthConfig},
+    request::RequestError,
+    sigv4::{self, SigV4Config},
+};
+
+/// How a request authenticates, as edited on the Auth tab.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Auth {
+    #[default]
+    None,
+    Basic {
+        username: String,
+        password: String,
+    },
+    Bearer {
+        token: String,
+    },
+    ApiKey {
+        name: String,
+        value: String,
+        location: ApiKeyLocation,
+    },
+    #[serde(rename = "oauth2")]
+    OAuth2(OAuthConfig),
+    #[serde(rename = "aws_sigv4")]
+    AwsSigV4(SigV4Config),
+    Hmac(HmacConfig),
+    Digest {
+        username: String,
+        password: String,
+    },
+}
+
+/// Where an API key is sent.
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyLocation {
+    #[default]
+    Header,
+    Query,
+}
+
+impl Auth {
+    /// Applies `f` to every text field, used to resolve `{{name}}`
+    /// placeholders.
+    pub fn map_text(&self, mut f: impl FnMut(&str) -> String) -> Auth {
+        match self {
+            Auth::None => Auth::None,
+            Auth::Basic { username, password } => Auth::Basic {
+                username: f(username),
+                password: f(password),
+            },
+            Auth::Bearer { token } => Auth::Bearer { token: f(token) },
+            Auth::ApiKey {
+                name,
+                value,
+                location,
+            } => Auth::ApiKey {
+                name: f(name),
+                value: f(value),
+                location: *location,
+            },
+            Auth::OAuth2(config) => Auth::OAuth2(OAuthConfig {
+                token_url: f(&config.token_url),
+                client_id: f(&config.client_id),
+                client_secret: f(&config.client_secret),
+                scope: f(&config.scope),
+                username: f(&config.username),
+                password: f(&config.password),
+                refresh_token: f(&config.refresh_token),
+                ..*config
+            }),
+            Auth::AwsSigV4(config) => Auth::AwsSigV4(SigV4Config {
+                access_key_id: f(&config.access_key_id),
+                secret_access_key: f(&config.secret_access_key),
+                session_token: f(&config.session_token),
+                region: f(&config.region),
+                service: f(&config.service),
+            }),
+            Auth::Digest { username, password } => Auth::Digest {
+                username: f(username),
+                password: f(password),
+            },
+            Auth::Hmac(config) => Auth::Hmac(HmacConfig {
+                secret: f(&config.secret),
+                string_to_sign: f(&config.string_to_sign),
+                header: f(&config.header),
+                header_value: f(&config.header_value),
+                timestamp_header: f(&config.timestamp_header),
+                ..*config
+            }),
+        }
+    }
+
+    /// A copy with passwords, tokens and keys left blank, for the history
+    /// file. Secrets that only refer to `{{variables}}` are kept, the
+    /// variables are what holds them.
+    pub fn without_secrets(&self) -> Auth {
+        let redact = |secret: &str| {
+            if placeholder_regex().replace_all(secret, "").trim().is_empty() {
+                secret.to_owned()
+            } else {
+                String::new()
+            }
+        };
+        match self {
+            Auth::None => Auth::None,
+            Auth::Basic { username, password } => Auth::Basic {
+                username: username.clone(),
+                password: redact(password),
+            },
+            Auth::Bearer { token } => Auth::Bearer {
+                token: redact(token),
+            },
+            Auth::ApiKey {
+                name,
+                value,
+                location,
+            } => Auth::ApiKey {
+                name: name.clone(),
+                value: redact(value),
+                location: *location,
+            },
+            Auth::OAuth2(config) => Auth::OAuth2(OAuthConfig {
+                client_secret: redact(&config.client_secret),
+                password: redact(&config.password),
+                refresh_token: redact(&config.refresh_token),
+                ..config.clone()
+            }),
+            Auth::AwsSigV4(config) => Auth::AwsSigV4(SigV4Config {
+                secret_access_key: redact(&config.secret_access_key),
+                session_token: redact(&config.session_token),
+                ..config.clone()
+            }),
+            Auth::Hmac(config) => Auth::Hmac(HmacConfig {
+                secret: redact(&config.secret),
+                ..config.clone()
+            }),
+            Auth::Digest { username, password } => Auth::Digest {
+                username: username.clone(),
+                password: redact(password),
+            },
+        }
+    }
+
+    /// Adds the credentials to a built request. They replace an
+    /// `Authorization` header set on the Headers tab. OAuth 2.0 uses the cached
+    /// token, `send_request` makes sure there is one. AWS SigV4 and HMAC sign
+    /// the request as built so far, so this has to be the last change to it.
+    /// Digest auth needs the server's challenge first and is answered in
+    /// `send_request`.
+    pub fn apply(&self, request: &mut Request) -> Result<(), RequestError> {
+        match self {
+            Auth::None | Auth::Digest { .. } => {}
+            Auth::OAuth2(config) => {
+                if let Some(token) = oauth::cached_access_token(config) {
+                    set_header(request, AUTHORIZATION, &format!("Bearer {token}"))?;
+                }
+            }
+            Auth::Basic { username, password } => {
+                let credentials = STANDARD.encode(format!("{username}:{password}"));
+                set_header(request, AUTHORIZATION, &format!("Basic {credentials}"))?;
+            }
+            Auth::Bearer { token } => {
+                set_header(request, AUTHORIZATION, &format!("Bearer {token}"))?;
+            }
+            Auth::ApiKey {
+                name,
+                value,
+                location: ApiKeyLocation::Header,
+            } => {
+                let name = HeaderName::from_bytes(name.trim().as_bytes()).map_err(|_| {
+                    RequestError::Other(format!("'{name}' is not a valid API key header name"))
+                })?;
+                set_header(request, name, value)?;
+            }
+            Auth::ApiKey {
+                name,
+                value,
+                location: ApiKeyLocation::Query,
+            } => {
+                request
+                    .url_mut()
+                    .query_pairs_mut()
+                    .append_pair(name.trim(), value);
+            }
+            Auth::AwsSigV4(config) => sigv4::sign(request, config, Utc::now())?,
+            Auth::Hmac(config) => hmac_auth::sign(request, config, Utc::now())?,
+        }
+        Ok(())
+    }
+}
+
+pub fn set_header(
+    request: &mut Request,
+    name: HeaderName,
+    value: &str,
+) -> Result<(), RequestError> {
+    let value = HeaderValue::from_str(value.trim()).map_err(|_| {
+        RequestError::Other(format!("The {name} header contains invalid characters"))
+    })?;
+    request.headers_mut().insert(name, value);
+    Ok(())
+}
+
+/// Reads the `auth` field of a saved request. Requests saved before the Auth
+/// tab had modes stored free text there, which was never sent and reads as no
+/// auth.
+pub fn deserialize_auth<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Auth, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StoredAuth {
+        Auth(Auth),
+        // Only checked to be a string, the text itself is dropped.
+        #[allow(dead_code)]
+        Text(String),
+    }
+
+    Ok(match StoredAuth::deserialize(deserializer)? {
+        StoredAuth::Auth(auth) => auth,
+        StoredAuth::Text(_) => Auth::None,
+    })
+}
@@ -0,0 +1,540 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Wrap},
+    Frame,
+};
+use regex::Regex;
+
+use crate::{
+    auth::{ApiKeyLocation, Auth},
+    hmac_auth::{HmacAlgorithm, HmacConfig, SignatureEncoding},
+    oauth::{ClientAuthentication, OAuthConfig, OAuthGrant, TokenState},
+    sigv4::SigV4Config,
+    text_input::TextInput,
+};
+
+const API_KEY_LOCATIONS: &[&str] = &["Header", "Query"];
+const OAUTH_GRANTS: &[&str] = &["Client credentials", "Password", "Refresh token"];
+const CLIENT_AUTHENTICATIONS: &[&str] = &["Basic auth header", "Request body"];
+const HMAC_ALGORITHMS: &[&str] = &["SHA-256", "SHA-1", "SHA-512"];
+const SIGNATURE_ENCODINGS: &[&str] = &["Hex", "Base64"];
+
+/// Width of the label column, the values start right after it.
+const LABEL_WIDTH: u16 = 18;
+
+/// The kinds of credentials the Auth tab edits, one per `Auth` variant.
+#[derive(Clone, Copy, PartialEq)]
+pub enum AuthMode {
+    None,
+    Basic,
+    Bearer,
+    ApiKey,
+    OAuth2,
+    AwsSigV4,
+    Hmac,
+    Digest,
+}
+
+impl AuthMode {
+    pub const ALL: [AuthMode; 8] = [
+        AuthMode::None,
+        AuthMode::Basic,
+        AuthMode::Bearer,
+        AuthMode::ApiKey,
+        AuthMode::OAuth2,
+        AuthMode::AwsSigV4,
+        AuthMode::Hmac,
+        AuthMode::Digest,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AuthMode::None => "None",
+            AuthMode::Basic => "Basic",
+            AuthMode::Bearer => "Bearer",
+            AuthMode::ApiKey => "API key",
+            AuthMode::OAuth2 => "OAuth 2.0",
+            AuthMode::AwsSigV4 => "AWS SigV4",
+            AuthMode::Hmac => "HMAC",
+            AuthMode::Digest => "Digest",
+        }
+    }
+
+    /// Position in `ALL`, which is also where the editor keeps its fields.
+    fn index(self) -> usize {
+        AuthMode::ALL
+            .iter()
+            .position(|mode| *mode == self)
+            .unwrap_or(0)
+    }
+
+    /// The next or previous mode, for cycling through them with the arrow
+    /// keys.
+    fn cycle(self, forward: bool) -> AuthMode {
+        let len = AuthMode::ALL.len();
+        let index = if forward {
+            (self.index() + 1) % len
+        } else {
+            (self.index() + len - 1) % len
+        };
+        AuthMode::ALL[index]
+    }
+
+    /// The fields the mode is edited with, in the order `set_auth` and
+    /// `to_auth` read them.
+    fn fields<'a>(self) -> Vec<AuthField<'a>> {
+        match self {
+            AuthMode::None => vec![],
+            AuthMode::Basic | AuthMode::Digest => vec![
+                AuthField::new("Username", FieldKind::Text),
+                AuthField::new("Password", FieldKind::Secret),
+            ],
+            AuthMode::Bearer => vec![AuthField::new("Token", FieldKind::Secret)],
+            AuthMode::ApiKey => vec![
+                AuthField::new("Add to", FieldKind::Choice(API_KEY_LOCATIONS)),
+                AuthField::new("Name", FieldKind::Text),
+                AuthField::new("Value", FieldKind::Secret),
+            ],
+            AuthMode::OAuth2 => vec![
+                AuthField::new("Grant", FieldKind::Choice(OAUTH_GRANTS)),
+                AuthField::new("Token URL", FieldKind::Text),
+                AuthField::new("Client ID", FieldKind::Text),
+                AuthField::new("Client secret", FieldKind::Secret),
+                AuthField::new("Scope", FieldKind::Text),
+                AuthField::new("Username", FieldKind::Text).shown_when(0, OAUTH_GRANTS[1]),
+                AuthField::new("Password", FieldKind::Secret).shown_when(0, OAUTH_GRANTS[1]),
+                AuthField::new("Refresh token", FieldKind::Secret).shown_when(0, OAUTH_GRANTS[2]),
+                AuthField::new("Client auth", FieldKind::Choice(CLIENT_AUTHENTICATIONS)),
+            ],
+            AuthMode::AwsSigV4 => vec![
+                AuthField::new("Access key ID", FieldKind::Text),
+                AuthField::new("Secret key", FieldKind::Secret),
+                AuthField::new("Session token", FieldKind::Secret),
+                AuthField::new("Region", FieldKind::Text),
+                AuthField::new("Service", FieldKind::Text),
+            ],
+            AuthMode::Hmac => vec![
+                AuthField::new("Algorithm", FieldKind::Choice(HMAC_ALGORITHMS)),
+                AuthField::new("Secret", FieldKind::Secret),
+                AuthField::new("Encoding", FieldKind::Choice(SIGNATURE_ENCODINGS)),
+                AuthField::new("String to sign", FieldKind::Text),
+                AuthField::new("Header", FieldKind::Text),
+                AuthField::new("Header value", FieldKind::Text),
+                AuthField::new("Timestamp header", FieldKind::Text),
+            ],
+        }
+    }
+}
+
+pub enum FieldKind {
+    Text,
+    /// Masked unless the field is being edited.
+    Secret,
+    /// One of a fixed set of values, cycled with the arrow keys.
+    Choice(&'static [&'static str]),
+}
+
+pub struct AuthField<'a> {
+    pub label: &'static str,
+    pub kind: FieldKind,
+    pub input: TextInput<'a>,
+    /// Only shown while the field at this index has this value.
+    pub shown_when: Option<(usize, &'static str)>,
+}
+
+impl<'a> AuthField<'a> {
+    fn new(label: &'static str, kind: FieldKind) -> Self {
+        let mut input = TextInput::new("");
+        if let FieldKind::Choice(choices) = kind {
+            input.set_text(choices[0]);
+        }
+        Self {
+            label,
+            kind,
+            input,
+            shown_when: None,
+        }
+    }
+
+    fn shown_when(mut self, field: usize, value: &'static str) -> Self {
+        self.shown_when = Some((field, value));
+        self
+    }
+
+    fn text(&self) -> &str {
+        self.input.text()
+    }
+
+    /// Index of the value among the choices.
+    fn choice(&self) -> usize {
+        let FieldKind::Choice(choices) = self.kind else {
+            return 0;
+        };
+        choices
+            .iter()
+            .position(|choice| *choice == self.input.text())
+            .unwrap_or(0)
+    }
+
+    fn set_choice(&mut self, index: usize) {
+        if let FieldKind::Choice(choices) = self.kind {
+            self.input.set_text(choices[index % choices.len()]);
+        }
+    }
+
+    fn cycle_choice(&mut self, forward: bool) {
+        let FieldKind::Choice(choices) = self.kind else {
+            return;
+        };
+        let index = if forward {
+            self.choice() + 1
+        } else {
+            self.choice() + choices.len() - 1
+        };
+        self.set_choice(index);
+    }
+}
+
+/// The Auth tab: a mode and the fields that mode needs. Every mode keeps its
+/// own fields, so switching back and forth does not lose what was typed.
+pub struct AuthEditor<'a> {
+    pub mode: AuthMode,
+    /// The fields of every mode, in the order of `AuthMode::ALL`.
+    pub fields: Vec<Vec<AuthField<'a>>>,
+    pub selected_field: usize,
+}
+
+impl<'a> AuthEditor<'a> {
+    pub fn new() -> Self {
+        let mut editor = Self {
+            mode: AuthMode::None,
+            fields: AuthMode::ALL.iter().map(|mode| mode.fields()).collect(),
+            selected_field: 0,
+        };
+        // The HMAC templates start out with a common scheme.
+        editor.set_auth(&Auth::Hmac(HmacConfig::default()));
+        editor.mode = AuthMode::None;
+        editor
+    }
+
+    pub fn from_auth(auth: &Auth) -> Self {
+        let mut editor = Self::new();
+        editor.set_auth(auth);
+        editor
+    }
+
+    /// Switches to the mode of `auth` and fills in its fields.
+    fn set_auth(&mut self, auth: &Auth) {
+        let (mode, values): (AuthMode, Vec<&str>) = match auth {
+            Auth::None => (AuthMode::None, vec![]),
+            Auth::Basic { username, password } => (AuthMode::Basic, vec![username, password]),
+            Auth::Bearer { token } => (AuthMode::Bearer, vec![token]),
+            Auth::ApiKey {
+                name,
+                value,
+                location,
+            } => {
+                let location = API_KEY_LOCATIONS[*location as usize];
+                (AuthMode::ApiKey, vec![location, name, value])
+            }
+            Auth::OAuth2(config) => (
+                AuthMode::OAuth2,
+                vec![
+                    OAUTH_GRANTS[config.grant as usize],
+                    &config.token_url,
+                    &config.client_id,
+                    &config.client_secret,
+                    &config.scope,
+                    &config.username,
+                    &config.password,
+                    &config.refresh_token,
+                    CLIENT_AUTHENTICATIONS[config.client_authentication as usize],
+                ],
+            ),
+            Auth::AwsSigV4(config) => (
+                AuthMode::AwsSigV4,
+                vec![
+                    &config.access_key_id,
+                    &config.secret_access_key,
+                    &config.session_token,
+                    &config.region,
+                    &config.service,
+                ],
+            ),
+            Auth::Hmac(config) => (
+                AuthMode::Hmac,
+                vec![
+                    HMAC_ALGORITHMS[config.algorithm as usize],
+                    &config.secret,
+                    SIGNATURE_ENCODINGS[config.encoding as usize],
+                    &config.string_to_sign,
+                    &config.header,
+                    &config.header_value,
+                    &config.timestamp_header,
+                ],
+            ),
+            Auth::Digest { username, password } => (AuthMode::Digest, vec![username, password]),
+        };
+        self.mode = mode;
+        for (field, value) in self.fields[mode.index()].iter_mut().zip(values) {
+            field.input.set_text(value);
+        }
+    }
+
+    /// The fields of the current mode.
+    fn mode_fields(&self) -> &[AuthField<'a>] {
+        &self.fields[self.mode.index()]
+    }
+
+    pub fn to_auth(&self) -> Auth {
+        let fields = self.mode_fields();
+        let value = |index: usize| fields[index].text().to_owned();
+        match self.mode {
+            AuthMode::Basic => Auth::Basic {
+                username: value(0),
+                password: value(1),
+            },
+            AuthMode::Bearer => Auth::Bearer { token: value(0) },
+            AuthMode::ApiKey => Auth::ApiKey {
+                location: match fields[0].choice() {
+                    1 => ApiKeyLocation::Query,
+                    _ => ApiKeyLocation::Header,
+                },
+                name: value(1),
+                value: value(2),
+            },
+            AuthMode::OAuth2 => Auth::OAuth2(OAuthConfig {
+                grant: match fields[0].choice() {
+                    1 => OAuthGrant::Password,
+                    2 => OAuthGrant::RefreshToken,
+                    _ => OAuthGrant::ClientCredentials,
+                },
+                token_url: value(1),
+                client_id: value(2),
+                client_secret: value(3),
+                scope: value(4),
+                username: value(5),
+                password: value(6),
+                refresh_token: value(7),
+                client_authentication: match fields[8].choice() {
+                    1 => ClientAuthentication::Body,
+                    _ => ClientAuthentication::BasicHeader,
+                },
+            }),
+            AuthMode::AwsSigV4 => Auth::AwsSigV4(SigV4Config {
+                access_key_id: value(0),
+                secret_access_key: value(1),
+                session_token: value(2),
+                region: value(3),
+                service: value(4),
+            }),
+            AuthMode::Hmac => Auth::Hmac(HmacConfig {
+                algorithm: match fields[0].choice() {
+                    1 => HmacAlgorithm::Sha1,
+                    2 => HmacAlgorithm::Sha512,
+                    _ => HmacAlgorithm::Sha256,
+                },
+                secret: value(1),
+                encoding: match fields[2].choice() {
+                    1 => SignatureEncoding::Base64,
+                    _ => SignatureEncoding::Hex,
+                },
+                string_to_sign: value(3),
+                header: value(4),
+                header_value: value(5),
+                timestamp_header: value(6),
+            }),
+            AuthMode::Digest => Auth::Digest {
+                username: value(0),
+                password: value(1),
+            },
+            AuthMode::None => Auth::None,
+        }
+    }
+
+    /// Texts of the current mode's fields, for placeholder highlighting.
+    pub fn texts(&self) -> impl Iterator<Item = &str> {
+        self.mode_fields().iter().map(AuthField::text)
+    }
+
+    /// Indices of the current mode's fields that are shown.
+    fn visible_fields(&self) -> Vec<usize> {
+        let fields = self.mode_fields();
+        (0..fields.len())
+            .filter(|&index| match fields[index].shown_when {
+                Some((other, value)) => fields[other].text() == value,
+                None => true,
+            })
+            .collect()
+    }
+
+    pub fn on_key(&mut self, key: &KeyEvent) {
+        let visible = self.visible_fields();
+        let position = visible
+            .iter()
+            .position(|&index| index == self.selected_field)
+            .unwrap_or(0);
+        match key.code {
+            KeyCode::Left | KeyCode::Right if key.modifiers == KeyModifiers::ALT => {
+                self.mode = self.mode.cycle(key.code == KeyCode::Right);
+                self.selected_field = 0;
+            }
+            KeyCode::Down if !visible.is_empty() => {
+                self.selected_field = visible[(position + 1) % visible.len()]
+            }
+            KeyCode::Up if !visible.is_empty() => {
+                self.selected_field = visible[(position + visible.len() - 1) % visible.len()]
+            }
+            _ => {
+                let Some(field) = self.fields[self.mode.index()].get_mut(self.selected_field)
+                else {
+                    return;
+                };
+                match (&field.kind, key.code) {
+                    (FieldKind::Choice(_), KeyCode::Left) => field.cycle_choice(false),
+                    (FieldKind::Choice(_), KeyCode::Right | KeyCode::Char(' ')) => {
+                        field.cycle_choice(true)
+                    }
+                    (FieldKind::Choice(_), _) => {}
+                    _ => field.input.on_input(key.code),
+                }
+            }
+        }
+    }
+}
+
+/// Draws the mode line and the current mode's fields, one per line. `focused`
+/// is set when the Auth tab is the selected input, which highlights the
+/// selected field and shows the cursor in it.
+pub fn render_auth_editor(
+    f: &mut Frame,
+    editor: &AuthEditor,
+    block: Block,
+    area: Rect,
+    focused: bool,
+    unresolved: Option<Regex>,
+    token_state: Option<TokenState>,
+) {
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let visible = editor.visible_fields();
+    let constraints: Vec<Constraint> = std::iter::once(Constraint::Length(2))
+        .chain(visible.iter().map(|_| Constraint::Length(1)))
+        .chain(std::iter::once(Constraint::Min(0)))
+        .collect();
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(inner);
+
+    let mode_line = Line::from(vec![
+        Span::raw("Mode: "),
+        Span::raw(format!("◂ {} ▸", editor.mode.label())).bold(),
+        Span::raw("  Alt + ←/→").fg(Color::DarkGray),
+    ]);
+    f.render_widget(Paragraph::new(mode_line), rows[0]);
+
+    let fields = editor.mode_fields();
+    for (&index, row) in visible.iter().zip(rows.iter().skip(1)) {
+        let field = &fields[index];
+        let selected = focused && index == editor.selected_field;
+        let [label_area, value_area] = [
+            Rect {
+                width: LABEL_WIDTH.min(row.width),
+                ..*row
+            },
+            Rect {
+                x: row.x + LABEL_WIDTH.min(row.width),
+                width: row.width.saturating_sub(LABEL_WIDTH),
+                ..*row
+            },
+        ];
+
+        let label = Paragraph::new(field.label);
+        let label = if selected {
+            label.fg(Color::Red).bold()
+        } else {
+            label.fg(Color::DarkGray)
+        };
+        f.render_widget(label, label_area);
+
+        match field.kind {
+            FieldKind::Choice(_) => {
+                let text = format!("◂ {} ▸", field.text());
+                f.render_widget(Paragraph::new(text), value_area);
+            }
+            FieldKind::Secret if !selected => {
+                let masked = "•".repeat(field.text().chars().count());
+                f.render_widget(Paragraph::new(masked), value_area);
+            }
+            FieldKind::Text | FieldKind::Secret => {
+                if selected {
+                    f.set_cursor(
+                        value_area.x + field.input.cursor_position() as u16,
+                        value_area.y,
+                    );
+                }
+                let input = field.input.clone().highlight(
+                    unresolved.clone(),
+                    Style::default().fg(Color::White).bg(Color::Red),
+                );
+                f.render_widget(input, value_area);
+            }
+        }
+    }
+
+    let footer = match token_state {
+        _ if visible.is_empty() => Line::styled(
+            "The request is sent without credentials.",
+            Style::default().fg(Color::DarkGray),
+        ),
+        Some(state) => token_state_line(state),
+        None if editor.mode == AuthMode::Hmac => Line::styled(
+            "Templates may use {method} {url} {host} {path} {query} {body} {timestamp} \
+             {timestamp_ms}, the header value also {signature}. \\n is a line break.",
+            Style::default().fg(Color::DarkGray),
+        ),
+        None => Line::raw(""),
+    };
+    let footer_area = rows[rows.len() - 1];
+    let footer_area = Rect {
+        y: footer_area.y + 1.min(footer_area.height),
+        height: footer_area.height.saturating_sub(1),
+        ..footer_area
+    };
+    f.render_widget(
+        Paragraph::new(footer).wrap(Wrap { trim: true }),
+        footer_area,
+    );
+}
+
+fn token_state_line(state: TokenState) -> Line<'static> {
+    match state {
+        TokenState::Missing => Line::styled(
+            "No token yet, one is fetched when the request is sent.",
+            Style::default().fg(Color::DarkGray),
+        ),
+        TokenState::Valid {
+            expires_in: Some(expires_in),
+        } => {
+            let seconds = expires_in.as_secs();
+            Line::styled(
+                format!("Token valid for {}m {}s.", seconds / 60, seconds % 60),
+                Style::default().fg(Color::Green),
+            )
+        }
+        TokenState::Valid { expires_in: None } => Line::styled(
+            "Token valid, it does not expire.",
+            Style::default().fg(Color::Green),
+        ),
+        TokenState::Expiring => Line::styled(
+            "Token expired, it is renewed when the request is sent.",
+            Style::default().fg(Color::Yellow),
+        ),
+        TokenState::Failed(err) => Line::styled(err, Style::default().fg(Color::Red)),
+    }
+}
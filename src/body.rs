@@ -1,10 +1,19 @@
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style, Stylize},
-    widgets::{Block, Borders, Tabs, Paragraph},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Tabs},
     Frame,
 };
-use crate::{app::{SelectionState, is_within_block}, App};
+use tui_textarea::{CursorMove, TextArea};
+use crate::{
+    app::{SelectionState, is_within_block},
+    auth_editor::render_auth_editor,
+    graphql::SchemaState,
+    key_value_editor::render_key_value_editor,
+    request_body::{self, BodyType},
+    response, App,
+};
 
 pub fn render_body(f: &mut Frame, app: &mut App, chunk: &Rect) {
     let blocks = Layout::default()
@@ -19,7 +28,7 @@ pub fn render_body(f: &mut Frame, app: &mut App, chunk: &Rect) {
     let response_side = blocks[1];
 
     render_request_side(f, app, request_side);
-    render_response_side(f, app, response_side);
+    response::render_response_side(f, app, response_side);
 }
 
 fn render_request_side(f: &mut Frame, app: &mut App, chunk: Rect) {
@@ -58,27 +67,360 @@ fn render_request_side(f: &mut Frame, app: &mut App, chunk: Rect) {
         .borders(Borders::ALL)
         .border_style(Style::new().red());
 
+    if let SelectionState::RequestTabs = app.selected_input {
+        tabs = tabs.block(selected_block_style.clone());
+    }
+    f.render_widget(tabs, tab_block);
+
+    let focused = matches!(app.selected_input, SelectionState::RequestBody);
+    let block = if focused {
+        selected_block_style
+    } else {
+        Block::default().borders(Borders::ALL)
+    };
+
     // "Query", "Headers", "Body", "Auth"
-    let text_area = match app.tabs.index {
-        0 => &mut app.widgets.query_params_text_area,
-        1 => &mut app.widgets.headers_text_area,
-        2 => &mut app.widgets.body_text_area,
-        3 => &mut app.widgets.auth_text_area,
+    match app.tabs.index {
+        0 => render_key_value_editor(
+            f,
+            &app.widgets.query_editor,
+            block,
+            request_block,
+            focused,
+            app.unresolved_pattern.clone(),
+        ),
+        1 => render_key_value_editor(
+            f,
+            &app.widgets.headers_editor,
+            block,
+            request_block,
+            focused,
+            app.unresolved_pattern.clone(),
+        ),
+        2 => render_body_editor(f, app, block, request_block, focused),
+        3 => render_auth_editor(
+            f,
+            &app.widgets.auth_editor,
+            block,
+            request_block,
+            focused,
+            app.unresolved_pattern.clone(),
+            app.oauth_token_state(),
+        ),
         _ => unreachable!("Index out of bounds for tabs."),
-    };
-    text_area.set_block(Block::default().borders(Borders::ALL));
+    }
+}
 
-    match app.selected_input {
-        SelectionState::RequestTabs => tabs = tabs.block(selected_block_style),
-        SelectionState::RequestBody => text_area.set_block(selected_block_style),
+/// The body type, the text area or form for it and, for JSON, why it does not
+/// parse.
+fn render_body_editor(f: &mut Frame, app: &mut App, block: Block, area: Rect, focused: bool) {
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .split(inner);
+
+    let mut type_line = vec![
+        Span::raw("Type: "),
+        Span::raw(format!("◂ {} ▸", app.body_type.name())).bold(),
+        Span::raw("  Alt + ←/→").fg(Color::DarkGray),
+    ];
+    match app.body_type {
+        BodyType::Json => type_line.push(Span::raw(", Alt + f format").fg(Color::DarkGray)),
+        BodyType::Binary => type_line.push(Span::raw(", Enter browse").fg(Color::DarkGray)),
+        BodyType::GraphQl => type_line.push(Span::raw(", Alt + r schema").fg(Color::DarkGray)),
         _ => {}
     }
+    f.render_widget(Paragraph::new(Line::from(type_line)), rows[0]);
 
-    f.render_widget(tabs, tab_block);
-    f.render_widget(text_area.widget(), request_block);
+    let body_text_area = &mut app.widgets.body_text_area;
+    match app.body_type {
+        BodyType::None => {
+            let message = Paragraph::new("The request has no body.").fg(Color::DarkGray);
+            f.render_widget(message, rows[1]);
+            return;
+        }
+        BodyType::Form | BodyType::Multipart => {
+            let editor = match app.body_type {
+                BodyType::Form => &app.widgets.form_editor,
+                _ => &app.widgets.multipart_editor,
+            };
+            // The form has a footer of its own where the error line would be.
+            let form_area = rows[1].union(rows[2]);
+            render_key_value_editor(
+                f,
+                editor,
+                Block::default(),
+                form_area,
+                focused,
+                app.unresolved_pattern.clone(),
+            );
+            return;
+        }
+        BodyType::Binary => {
+            render_body_file(f, app, rows[1], focused);
+            return;
+        }
+        BodyType::GraphQl => {
+            render_graphql_editor(f, app, rows[1], rows[2], focused);
+            return;
+        }
+        BodyType::Raw => body_text_area.remove_line_number(),
+        BodyType::Json | BodyType::Xml => {
+            body_text_area.set_line_number_style(Style::default().fg(Color::DarkGray))
+        }
+    }
+    f.render_widget(body_text_area.widget(), rows[1]);
+
+    if let Some(err) = &app.body_error {
+        highlight_error(f, body_text_area, rows[1], err);
+        let message = Paragraph::new(format!("✗ {err}")).fg(Color::Red);
+        f.render_widget(message, rows[2]);
+    }
 }
 
-fn render_response_side(f: &mut Frame, app: &mut App, chunk: Rect) {
-    let paragraph = Paragraph::new(app.response_text.clone()).block(Block::default().borders(Borders::ALL));
-    f.render_widget(paragraph, chunk);
-}
\ No newline at end of file
+/// The path of the file to send, with its size and the `Content-Type` it is
+/// sent with.
+fn render_body_file(f: &mut Frame, app: &App, area: Rect, focused: bool) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2),
+            Constraint::Length(1),
+            Constraint::Min(0),
+        ])
+        .split(area);
+
+    let input = &app.widgets.body_file_input;
+    let label = "File: ";
+    let input_area = Rect {
+        x: rows[0].x + label.len() as u16,
+        width: rows[0].width.saturating_sub(label.len() as u16),
+        height: 1,
+        ..rows[0]
+    };
+    f.render_widget(Paragraph::new(label).fg(Color::DarkGray), rows[0]);
+    let input_widget = input.clone().highlight(
+        app.unresolved_pattern.clone(),
+        Style::default().fg(Color::White).bg(Color::Red),
+    );
+    f.render_widget(input_widget, input_area);
+    if focused {
+        f.set_cursor(input_area.x + input.cursor_position() as u16, input_area.y);
+    }
+
+    let path = input.text().trim();
+    let overridden = app
+        .widgets
+        .headers_editor
+        .enabled_pairs()
+        .any(|(key, _)| key.trim().eq_ignore_ascii_case("content-type"));
+    let info = if path.is_empty() {
+        Line::styled("No file chosen.", Style::default().fg(Color::DarkGray))
+    } else if path.contains("{{") {
+        // Resolved when the request is sent.
+        Line::default()
+    } else {
+        match app.body_file_size {
+            Some(size) => {
+                let content_type = if overridden {
+                    "Content-Type from the Headers tab".to_owned()
+                } else {
+                    format!("Content-Type: {}", request_body::guess_content_type(path))
+                };
+                Line::styled(
+                    format!("{}, {content_type}", response::format_size(size as usize)),
+                    Style::default().fg(Color::DarkGray),
+                )
+            }
+            None => Line::styled(
+                format!("'{path}' is not a file"),
+                Style::default().fg(Color::Red),
+            ),
+        }
+    };
+    f.render_widget(Paragraph::new(info), rows[1]);
+}
+
+/// The GraphQL query above its variables. The line below them completes field
+/// names, tells why the variables do not parse or where the schema stands.
+fn render_graphql_editor(
+    f: &mut Frame,
+    app: &mut App,
+    area: Rect,
+    status_area: Rect,
+    focused: bool,
+) {
+    let panes = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(65), Constraint::Percentage(35)])
+        .split(area);
+
+    let variables_focused = app.graphql_variables_focused;
+    let pane_block = |title: &str, active: bool, switch_key: &str| {
+        let title = if active {
+            Line::from(Span::raw(title.to_owned()).bold())
+        } else {
+            Line::from(vec![
+                Span::raw(title.to_owned()),
+                Span::raw(format!(" {switch_key}")).fg(Color::DarkGray),
+            ])
+        };
+        Block::default().borders(Borders::TOP).title(title)
+    };
+    let query_block = pane_block("Query", !variables_focused, "Alt + ↑");
+    let variables_block = pane_block("Variables", variables_focused, "Alt + ↓");
+    let query_area = query_block.inner(panes[0]);
+    let variables_area = variables_block.inner(panes[1]);
+    f.render_widget(query_block, panes[0]);
+    f.render_widget(variables_block, panes[1]);
+
+    let widgets = &mut app.widgets;
+    for (text_area, active) in [
+        (&mut widgets.graphql_query_area, !variables_focused),
+        (&mut widgets.graphql_variables_area, variables_focused),
+    ] {
+        text_area.set_line_number_style(Style::default().fg(Color::DarkGray));
+        // Only the pane that takes the keys shows a cursor.
+        if active {
+            text_area.set_cursor_style(Style::default().add_modifier(Modifier::REVERSED));
+            text_area.set_cursor_line_style(Style::default().add_modifier(Modifier::UNDERLINED));
+        } else {
+            text_area.set_cursor_style(Style::default());
+            text_area.set_cursor_line_style(Style::default());
+        }
+    }
+    f.render_widget(widgets.graphql_query_area.widget(), query_area);
+    f.render_widget(widgets.graphql_variables_area.widget(), variables_area);
+    if let Some(err) = &app.body_error {
+        highlight_error(f, &mut widgets.graphql_variables_area, variables_area, err);
+    }
+
+    let completion = app
+        .graphql_completion()
+        .filter(|completion| focused && !variables_focused && !completion.fields.is_empty());
+    let status = if let Some(err) = &app.body_error {
+        Line::styled(format!("✗ {err}"), Style::default().fg(Color::Red))
+    } else if let Some(completion) = completion {
+        let mut spans = vec![Span::raw("Tab ").fg(Color::DarkGray)];
+        for field in completion.fields {
+            spans.push(Span::raw(field.name.clone()));
+            spans.push(Span::raw(format!(": {}  ", field.signature)).fg(Color::DarkGray));
+        }
+        Line::from(spans)
+    } else {
+        match &app.graphql_schema {
+            SchemaState::None => Line::styled(
+                "Alt + r fetches the schema to complete field names",
+                Style::default().fg(Color::DarkGray),
+            ),
+            SchemaState::Loading(_) => Line::styled(
+                "Fetching the schema...",
+                Style::default().fg(Color::DarkGray),
+            ),
+            SchemaState::Loaded(schema) => Line::styled(
+                format!("Schema of {}: {} types", schema.url, schema.type_count()),
+                Style::default().fg(Color::DarkGray),
+            ),
+            SchemaState::Failed(err) => Line::styled(
+                format!("✗ Schema: {err}"),
+                Style::default().fg(Color::Red),
+            ),
+        }
+    };
+    f.render_widget(Paragraph::new(status), status_area);
+}
+
+/// Marks the line where the JSON in `text_area` stops parsing in red, and the
+/// character it stops at in reverse. Called right after the text area is
+/// rendered to `area`, as its scroll position is only known then.
+fn highlight_error(f: &mut Frame, text_area: &mut TextArea, area: Rect, err: &serde_json::Error) {
+    let lines = text_area.lines();
+    let row = err.line().saturating_sub(1).min(lines.len() - 1);
+    // serde_json counts the column in bytes.
+    let line = &lines[row];
+    let column = line
+        .char_indices()
+        .position(|(index, _)| index + 1 >= err.column())
+        .unwrap_or(line.chars().count());
+    let Some((top_row, top_column)) = viewport_top(text_area, area.height) else {
+        return;
+    };
+    if row < top_row || row >= top_row + area.height as usize {
+        return;
+    }
+
+    let y = area.y + (row - top_row) as u16;
+    f.buffer_mut()
+        .set_style(Rect { y, height: 1, ..area }, Style::default().fg(Color::Red));
+
+    let Some(top_column) = top_column else {
+        return;
+    };
+    // Same layout as tui-textarea: the line number gutter, then the text with
+    // tabs expanded, both scrolled by the viewport's first column.
+    let gutter = match text_area.line_number_style() {
+        Some(_) => text_area.lines().len().to_string().len() + 2,
+        None => 0,
+    };
+    let tab_length = text_area.tab_length().max(1) as usize;
+    let mut x = 0;
+    for c in text_area.lines()[row].chars().take(column) {
+        x += match c {
+            '\t' => tab_length - x % tab_length,
+            c => Span::raw(c.to_string()).width(),
+        };
+    }
+    let Some(x) = (gutter + x).checked_sub(top_column) else {
+        return;
+    };
+    if x < area.width as usize {
+        let cell = Rect {
+            x: area.x + x as u16,
+            y,
+            width: 1,
+            height: 1,
+        };
+        f.buffer_mut().set_style(
+            cell,
+            Style::default().fg(Color::Red).add_modifier(Modifier::REVERSED),
+        );
+    }
+}
+
+/// The first row and column the text area showed when it was last rendered.
+/// tui-textarea keeps its viewport private, so the cursor is moved into it
+/// and back. The column is unknown when every line on screen is scrolled out
+/// of view sideways.
+fn viewport_top(text_area: &mut TextArea, height: u16) -> Option<(usize, Option<usize>)> {
+    let (row, column) = text_area.cursor();
+    let cursor = CursorMove::Jump(u16::try_from(row).ok()?, u16::try_from(column).ok()?);
+
+    text_area.move_cursor(CursorMove::Jump(0, 0));
+    text_area.move_cursor(CursorMove::InViewport);
+    let top_row = text_area.cursor().0;
+
+    // The cursor lands on the first column shown, unless the line is too
+    // short to reach it. The longest line on screen is the best bet.
+    let (longest, length) = text_area
+        .lines()
+        .iter()
+        .enumerate()
+        .skip(top_row)
+        .take(height as usize)
+        .map(|(index, line)| (index, line.chars().count()))
+        .max_by_key(|(_, length)| *length)
+        .unwrap_or((top_row, 0));
+    text_area.move_cursor(CursorMove::Jump(longest as u16, 0));
+    text_area.move_cursor(CursorMove::InViewport);
+    let top_column = text_area.cursor().1;
+
+    text_area.move_cursor(cursor);
+    Some((top_row, (top_column < length).then_some(top_column)))
+}
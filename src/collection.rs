@@ -0,0 +1,275 @@
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{
+    auth::{self, Auth},
+    environment::PROJECT_DIR,
+    request_body::{self, BodyType},
+};
+
+const COLLECTIONS_DIR: &str = "collections";
+const REQUEST_EXTENSION: &str = "toml";
+
+/// A request as stored on disk. Every request is its own TOML file so changes
+/// show up as small, readable diffs.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SavedRequest {
+    pub method: String,
+    pub url: String,
+    #[serde(deserialize_with = "deserialize_key_values")]
+    pub query: Vec<KeyValue>,
+    #[serde(deserialize_with = "deserialize_key_values")]
+    pub headers: Vec<KeyValue>,
+    #[serde(default = "request_body::legacy_body_type")]
+    pub body_type: BodyType,
+    pub body: String,
+    /// Rows of the URL-encoded form body.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub form: Vec<KeyValue>,
+    /// Rows of the multipart form body.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub multipart: Vec<KeyValue>,
+    /// Path of the file sent as a binary body.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub body_file: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub graphql_query: String,
+    /// The GraphQL variables as JSON.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub graphql_variables: String,
+    #[serde(deserialize_with = "auth::deserialize_auth")]
+    pub auth: Auth,
+}
+
+/// A row of the Query or Headers tab, or of a form body.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct KeyValue {
+    pub key: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    pub enabled: bool,
+    /// The value is the path of a file to upload, in multipart forms.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub file: bool,
+}
+
+impl Default for KeyValue {
+    fn default() -> Self {
+        Self {
+            key: String::new(),
+            value: String::new(),
+            description: String::new(),
+            enabled: true,
+            file: false,
+        }
+    }
+}
+
+impl KeyValue {
+    pub fn new(key: &str, value: &str) -> Self {
+        Self {
+            key: key.to_owned(),
+            value: value.to_owned(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Reads query params and headers. Requests saved before the Query and
+/// Headers tabs were tables stored `key:value` lines.
+fn deserialize_key_values<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<KeyValue>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StoredKeyValue {
+        Row(KeyValue),
+        Line(String),
+    }
+
+    Ok(Vec::<StoredKeyValue>::deserialize(deserializer)?
+        .into_iter()
+        .map(|stored| match stored {
+            StoredKeyValue::Row(row) => row,
+            StoredKeyValue::Line(line) => {
+                let (key, value) = line.split_once(':').unwrap_or((&line, ""));
+                KeyValue::new(key.trim(), value.trim())
+            }
+        })
+        .collect())
+}
+
+/// A folder or request in the collections tree, flattened for display.
+pub struct CollectionEntry {
+    /// Path relative to the collections directory, without the extension for
+    /// requests.
+    pub path: PathBuf,
+    pub depth: usize,
+    pub is_folder: bool,
+}
+
+impl CollectionEntry {
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+pub fn collections_dir() -> PathBuf {
+    PathBuf::from(PROJECT_DIR).join(COLLECTIONS_DIR)
+}
+
+fn request_file(path: &Path) -> PathBuf {
+    // Not `with_extension`, request names may contain dots.
+    collections_dir().join(format!("{}.{REQUEST_EXTENSION}", path.display()))
+}
+
+/// Turns a user supplied name like `users/list` into a path inside the
+/// collections directory, refusing anything that would escape it.
+pub fn parse_request_path(name: &str) -> Result<PathBuf, String> {
+    let name = name.trim().trim_end_matches(".toml");
+    let path = PathBuf::from(name);
+    let is_valid = !name.is_empty()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+
+    if is_valid {
+        Ok(path)
+    } else {
+        Err(format!("'{name}' is not a valid request name"))
+    }
+}
+
+/// Lists the collections directory depth first, folders before requests.
+/// Children of folders in `collapsed` are left out.
+pub fn load_tree(collapsed: &HashSet<PathBuf>) -> io::Result<Vec<CollectionEntry>> {
+    let mut entries = Vec::new();
+    let root = collections_dir();
+    if root.is_dir() {
+        load_folder(&root, Path::new(""), 0, collapsed, &mut entries)?;
+    }
+    Ok(entries)
+}
+
+fn load_folder(
+    root: &Path,
+    folder: &Path,
+    depth: usize,
+    collapsed: &HashSet<PathBuf>,
+    entries: &mut Vec<CollectionEntry>,
+) -> io::Result<()> {
+    let mut children: Vec<(bool, PathBuf)> = Vec::new();
+    for dir_entry in fs::read_dir(root.join(folder))? {
+        let dir_entry = dir_entry?;
+        let file_path = dir_entry.path();
+        if file_path.is_dir() {
+            children.push((true, folder.join(dir_entry.file_name())));
+        } else if file_path
+            .extension()
+            .is_some_and(|ext| ext == REQUEST_EXTENSION)
+        {
+            let file_name = PathBuf::from(dir_entry.file_name());
+            children.push((false, folder.join(file_name.with_extension(""))));
+        }
+    }
+    children
+        .sort_by(|(a_is_folder, a), (b_is_folder, b)| b_is_folder.cmp(a_is_folder).then(a.cmp(b)));
+
+    for (is_folder, path) in children {
+        let expanded = is_folder && !collapsed.contains(&path);
+        entries.push(CollectionEntry {
+            path: path.clone(),
+            depth,
+            is_folder,
+        });
+        if expanded {
+            load_folder(root, &path, depth + 1, collapsed, entries)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn load_request(path: &Path) -> Result<SavedRequest, String> {
+    let file = request_file(path);
+    let text = fs::read_to_string(&file).map_err(|err| format!("{}: {err}", file.display()))?;
+    toml::from_str(&text).map_err(|err| format!("{}: {err}", file.display()))
+}
+
+pub fn save_request(path: &Path, request: &SavedRequest) -> Result<(), String> {
+    let file = request_file(path);
+    let text = toml::to_string_pretty(request).map_err(|err| err.to_string())?;
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent).map_err(|err| format!("{}: {err}", parent.display()))?;
+    }
+    fs::write(&file, text).map_err(|err| format!("{}: {err}", file.display()))
+}
+
+fn entry_file(path: &Path, is_folder: bool) -> PathBuf {
+    if is_folder {
+        collections_dir().join(path)
+    } else {
+        request_file(path)
+    }
+}
+
+/// Renames or moves a request or folder. `new_path` is relative to the
+/// collections directory, like every other path here.
+pub fn rename_entry(path: &Path, new_path: &Path, is_folder: bool) -> Result<(), String> {
+    let from = entry_file(path, is_folder);
+    let to = entry_file(new_path, is_folder);
+    if to.exists() {
+        return Err(format!("{} already exists", new_path.display()));
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).map_err(|err| format!("{}: {err}", parent.display()))?;
+    }
+    fs::rename(&from, &to).map_err(|err| format!("{}: {err}", from.display()))
+}
+
+/// Copies a request next to the original as "<name> copy", "<name> copy 2", ...
+/// and returns the path of the copy.
+pub fn duplicate_request(path: &Path) -> Result<PathBuf, String> {
+    let request = load_request(path)?;
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let mut copy_number = 1;
+    let copy_path = loop {
+        let copy_name = match copy_number {
+            1 => format!("{name} copy"),
+            n => format!("{name} copy {n}"),
+        };
+        let copy_path = path.with_file_name(copy_name);
+        if !request_file(&copy_path).exists() {
+            break copy_path;
+        }
+        copy_number += 1;
+    };
+
+    save_request(&copy_path, &request)?;
+    Ok(copy_path)
+}
+
+pub fn delete_entry(path: &Path, is_folder: bool) -> Result<(), String> {
+    let file = entry_file(path, is_folder);
+    let result = if is_folder {
+        fs::remove_dir_all(&file)
+    } else {
+        fs::remove_file(&file)
+    };
+    result.map_err(|err| format!("{}: {err}", file.display()))
+}
@@ -0,0 +1,36 @@
+use std::fmt::Write;
+
+use openssl::{
+    hash::{hash, MessageDigest},
+    pkey::PKey,
+    sign::Signer,
+};
+
+use crate::request::RequestError;
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex(&openssl::sha::sha256(data))
+}
+
+pub fn hash_hex(digest: MessageDigest, data: &[u8]) -> Result<String, RequestError> {
+    hash(digest, data)
+        .map(|digest| hex(&digest))
+        .map_err(|err| RequestError::Other(format!("Hashing failed: {err}")))
+}
+
+pub fn hmac(digest: MessageDigest, key: &[u8], data: &[u8]) -> Result<Vec<u8>, RequestError> {
+    let sign = || {
+        let key = PKey::hmac(key)?;
+        let mut signer = Signer::new(digest, &key)?;
+        signer.update(data)?;
+        signer.sign_to_vec()
+    };
+    sign().map_err(|err| RequestError::Other(format!("Signing failed: {err}")))
+}
@@ -0,0 +1,453 @@
+use std::fs;
+
+use crate::{
+    auth::Auth,
+    collection::{KeyValue, SavedRequest},
+    graphql, query,
+    request_body::BodyType,
+};
+
+/// Options that take a value but do not change the request itself.
+const IGNORED_VALUE_OPTIONS: &[&str] = &[
+    "-o",
+    "--output",
+    "-m",
+    "--max-time",
+    "--connect-timeout",
+    "-w",
+    "--write-out",
+    "--retry",
+    "-x",
+    "--proxy",
+    "-c",
+    "--cookie-jar",
+    "--cacert",
+    "-E",
+    "--cert",
+    "--key",
+    "--resolve",
+];
+
+/// Options without a value that do not change the request itself.
+const IGNORED_FLAGS: &[&str] = &[
+    "--compressed",
+    "-L",
+    "--location",
+    "-s",
+    "--silent",
+    "-S",
+    "--show-error",
+    "-v",
+    "--verbose",
+    "-i",
+    "--include",
+    "-f",
+    "--fail",
+    "-g",
+    "--globoff",
+    "-N",
+    "--no-buffer",
+    "--http1.1",
+    "--http2",
+];
+
+/// Short options that take a value, which may be attached as in `-XPOST`.
+const SHORT_VALUE_OPTIONS: &str = "XHduFAebomwxcET";
+
+/// Turns a curl command line, as copied from documentation or browser devtools,
+/// into a request for the editor. Also returns what of the command could not
+/// be kept, for telling the user.
+pub fn parse(command: &str) -> Result<(SavedRequest, Vec<&'static str>), String> {
+    let mut words = split_words(command)?.into_iter();
+    match words.next() {
+        Some(program) if program == "curl" || program.ends_with("/curl") => {}
+        _ => return Err("expected a command starting with 'curl'".to_owned()),
+    }
+
+    let mut options = Vec::new();
+    while let Some(word) = words.next() {
+        if word.starts_with("--") || !word.starts_with('-') || word == "-" {
+            let value = if takes_value(&word) {
+                Some(
+                    words
+                        .next()
+                        .ok_or_else(|| format!("option {word} needs a value"))?,
+                )
+            } else {
+                None
+            };
+            options.push((word, value));
+            continue;
+        }
+
+        // Short options can be bundled, as in `-sSL`, and the last one may
+        // have its value attached, as in `-XPOST`.
+        let mut flags = word[1..].chars();
+        while let Some(flag) = flags.next() {
+            let option = format!("-{flag}");
+            if SHORT_VALUE_OPTIONS.contains(flag) {
+                let attached: String = flags.by_ref().collect();
+                let value = if attached.is_empty() {
+                    words
+                        .next()
+                        .ok_or_else(|| format!("option {option} needs a value"))?
+                } else {
+                    attached
+                };
+                options.push((option, Some(value)));
+            } else {
+                options.push((option, None));
+            }
+        }
+    }
+
+    let mut command = CurlCommand::default();
+    for (option, value) in options {
+        command.apply(&option, value)?;
+    }
+    let mut warnings = Vec::new();
+    if command.insecure {
+        warnings.push("-k is ignored, TLS stays verified");
+    }
+    Ok((command.into_request()?, warnings))
+}
+
+/// Whether `command` stops inside a quoted argument, as a multi-line body
+/// does while it is being pasted.
+pub fn is_unterminated(command: &str) -> bool {
+    split_words(command).is_err_and(|err| err.starts_with("unterminated"))
+}
+
+fn takes_value(option: &str) -> bool {
+    IGNORED_VALUE_OPTIONS.contains(&option)
+        || matches!(
+            option,
+            "--request"
+                | "--header"
+                | "--data"
+                | "--data-ascii"
+                | "--data-binary"
+                | "--data-raw"
+                | "--json"
+                | "--user"
+                | "--form"
+                | "--form-string"
+                | "--upload-file"
+                | "--user-agent"
+                | "--referer"
+                | "--cookie"
+                | "--url"
+        )
+}
+
+#[derive(Default)]
+struct CurlCommand {
+    method: Option<String>,
+    url: Option<String>,
+    headers: Vec<String>,
+    data: Vec<String>,
+    form: Vec<KeyValue>,
+    /// Set by `--json`.
+    json: bool,
+    /// Sent as a binary body, from `--data-binary @path` or `-T`.
+    body_file: Option<String>,
+    /// Set by `-T`, which defaults to PUT.
+    upload: bool,
+    user: Option<String>,
+    get: bool,
+    head: bool,
+    /// Set by `-k`, which is not honoured: certificates are always verified.
+    insecure: bool,
+}
+
+impl CurlCommand {
+    fn apply(&mut self, option: &str, value: Option<String>) -> Result<(), String> {
+        let value = value.unwrap_or_default();
+        match option {
+            "-X" | "--request" => self.method = Some(value.to_uppercase()),
+            "-H" | "--header" => self.headers.push(value),
+            // Files are sent as they are, without reading them into the editor.
+            "--data-binary" if value.starts_with('@') => {
+                self.body_file = Some(value[1..].to_owned())
+            }
+            "-T" | "--upload-file" => {
+                self.body_file = Some(value);
+                self.upload = true;
+            }
+            "-d" | "--data" | "--data-ascii" | "--data-binary" => self.data.push(read_data(value)?),
+            "--data-raw" => self.data.push(value),
+            "--json" => {
+                // The JSON body type sets the Content-Type.
+                self.data.push(read_data(value)?);
+                self.json = true;
+                self.add_default_header("Accept", "application/json");
+            }
+            "-F" | "--form" => self.form.push(form_part(&value)?),
+            "--form-string" => {
+                let (name, value) = value.split_once('=').unwrap_or((&value, ""));
+                self.form.push(KeyValue::new(name, value));
+            }
+            "-u" | "--user" => self.user = Some(value),
+            "-A" | "--user-agent" => self.headers.push(format!("User-Agent: {value}")),
+            "-e" | "--referer" => self.headers.push(format!("Referer: {value}")),
+            // Without a `=` the value names a cookie file, which is not ours to read.
+            "-b" | "--cookie" if value.contains('=') => {
+                self.headers.push(format!("Cookie: {value}"))
+            }
+            "-b" | "--cookie" => {}
+            "-G" | "--get" => self.get = true,
+            "-I" | "--head" => self.head = true,
+            "-k" | "--insecure" => self.insecure = true,
+            "--url" => self.set_url(value)?,
+            _ if IGNORED_VALUE_OPTIONS.contains(&option) || IGNORED_FLAGS.contains(&option) => {}
+            _ if option.starts_with('-') => {
+                return Err(format!("unsupported curl option {option}"))
+            }
+            _ => self.set_url(option.to_owned())?,
+        }
+        Ok(())
+    }
+
+    fn set_url(&mut self, url: String) -> Result<(), String> {
+        if self.url.is_some() {
+            return Err("only a single URL can be imported".to_owned());
+        }
+        self.url = Some(url);
+        Ok(())
+    }
+
+    fn header_value(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find_map(|header| {
+            let (key, value) = header.split_once(':')?;
+            key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    }
+
+    fn has_header(&self, name: &str) -> bool {
+        self.header_value(name).is_some()
+    }
+
+    fn add_default_header(&mut self, name: &str, value: &str) {
+        if !self.has_header(name) {
+            self.headers.push(format!("{name}: {value}"));
+        }
+    }
+
+    fn into_request(mut self) -> Result<SavedRequest, String> {
+        let url = self.url.take().ok_or("the curl command has no URL")?;
+        // curl assumes http when the scheme is left out.
+        let url = if url.contains("://") {
+            url
+        } else {
+            format!("http://{url}")
+        };
+        let (url, url_query, _) = query::split_url(&url);
+        let mut query = query_rows(url_query.unwrap_or_default());
+        let data = self.data.join("&");
+        let url = url.to_owned();
+        let mut body = String::new();
+        if self.get {
+            query.extend(query_rows(&data));
+        } else if !self.data.is_empty() {
+            body = data;
+            self.add_default_header("Content-Type", "application/x-www-form-urlencoded");
+        }
+
+        let content_type = self.header_value("Content-Type").unwrap_or_default();
+        let body_type = if self.body_file.is_some() && body.is_empty() {
+            BodyType::Binary
+        } else if !self.form.is_empty() && body.is_empty() {
+            BodyType::Multipart
+        } else if body.is_empty() {
+            BodyType::None
+        } else if self.json || content_type.contains("json") {
+            BodyType::Json
+        } else if content_type.contains("xml") {
+            BodyType::Xml
+        } else {
+            BodyType::Raw
+        };
+        // Copied from a GraphQL client, the query and variables are posted as JSON.
+        let (body_type, body, graphql_query, graphql_variables) = match body_type {
+            BodyType::Json => match graphql::parse_request_body(&body) {
+                Some((query, variables)) => (BodyType::GraphQl, String::new(), query, variables),
+                None => (body_type, body, String::new(), String::new()),
+            },
+            _ => (body_type, body, String::new(), String::new()),
+        };
+
+        let method = match self.method {
+            Some(method) => method,
+            None if self.head => "HEAD".to_owned(),
+            None if self.upload => "PUT".to_owned(),
+            None if body_type != BodyType::None => "POST".to_owned(),
+            None => "GET".to_owned(),
+        };
+
+        let auth = match self.user {
+            Some(user) => {
+                let (username, password) = user.split_once(':').unwrap_or((&user, ""));
+                Auth::Basic {
+                    username: username.to_owned(),
+                    password: password.to_owned(),
+                }
+            }
+            None => Auth::None,
+        };
+
+        Ok(SavedRequest {
+            method,
+            url,
+            query,
+            headers: self
+                .headers
+                .iter()
+                .map(|header| {
+                    let (name, value) = header.split_once(':').unwrap_or((header, ""));
+                    KeyValue::new(name.trim(), value.trim())
+                })
+                .collect(),
+            body_type,
+            body,
+            multipart: self.form,
+            body_file: self.body_file.unwrap_or_default(),
+            graphql_query,
+            graphql_variables,
+            auth,
+            ..SavedRequest::default()
+        })
+    }
+}
+
+/// Decodes `a=1&b=2` into rows of the Query tab.
+fn query_rows(query: &str) -> Vec<KeyValue> {
+    query::parse(query)
+        .into_iter()
+        .map(|(key, value)| KeyValue::new(&key, &value))
+        .collect()
+}
+
+/// `-d @file` reads the body from a file, like curl does.
+fn read_data(value: String) -> Result<String, String> {
+    match value.strip_prefix('@') {
+        Some(path) => fs::read_to_string(path).map_err(|err| format!("{path}: {err}")),
+        None => Ok(value),
+    }
+}
+
+/// `-F name=value` is a text field, `name=@path` uploads a file and
+/// `name=<path` sends the contents of a file as text. Options after a `;`,
+/// like `;type=image/png`, are dropped.
+fn form_part(value: &str) -> Result<KeyValue, String> {
+    let (name, value) = value.split_once('=').unwrap_or((value, ""));
+    if let Some(path) = value.strip_prefix('@') {
+        let path = path.split(';').next().unwrap_or_default();
+        return Ok(KeyValue {
+            file: true,
+            ..KeyValue::new(name, path)
+        });
+    }
+    if let Some(path) = value.strip_prefix('<') {
+        let path = path.split(';').next().unwrap_or_default();
+        let text = fs::read_to_string(path).map_err(|err| format!("{path}: {err}"))?;
+        return Ok(KeyValue::new(name, &text));
+    }
+    Ok(KeyValue::new(name, value))
+}
+
+/// Splits a command line into words the way a POSIX shell would, handling
+/// single and double quotes, `$'...'` strings, backslash escapes and line
+/// continuations.
+fn split_words(command: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if let Some(word) = word.take() {
+                    words.push(word);
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some('\r') if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                }
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+                None => {}
+            },
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated ' quote".to_owned()),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('\n') => {}
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err("unterminated \" quote".to_owned()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated \" quote".to_owned()),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => word.push(ansi_c_escape(&mut chars)?),
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated $' quote".to_owned()),
+                    }
+                }
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if let Some(word) = word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// Decodes the escape after a backslash inside `$'...'`, which browsers use
+/// when a body contains quotes or newlines.
+fn ansi_c_escape(chars: &mut impl Iterator<Item = char>) -> Result<char, String> {
+    let escape = chars.next().ok_or("unterminated $' quote")?;
+    let code_point = |chars: &mut dyn Iterator<Item = char>, digits: usize| {
+        let hex: String = chars.take(digits).collect();
+        u32::from_str_radix(&hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| format!("invalid escape \\{escape}{hex}"))
+    };
+
+    Ok(match escape {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        'x' => code_point(chars, 2)?,
+        'u' => code_point(chars, 4)?,
+        'U' => code_point(chars, 8)?,
+        c => c,
+    })
+}
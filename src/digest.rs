@@ -0,0 +1,305 @@
+use std::{collections::HashMap, time::Instant};
+
+use openssl::{hash::MessageDigest, rand::rand_bytes};
+use reqwest::{
+    blocking::{Client, Request, Response},
+    header::{AUTHORIZATION, WWW_AUTHENTICATE},
+    StatusCode,
+};
+
+use crate::{
+    auth::set_header,
+    crypto::{hash_hex, hex},
+    request::{AuthChallenge, RequestError},
+};
+
+/// The hash functions of RFC 7616, strongest first. The `-sess` variants hash
+/// the client nonce into the key.
+const ALGORITHMS: [&str; 4] = ["SHA-256", "SHA-256-sess", "MD5", "MD5-sess"];
+
+/// Sends the request and, if the server answers with a Digest challenge,
+/// sends it again with the response to that challenge. The first leg is
+/// returned alongside the final response when there was one.
+pub fn execute(
+    client: &Client,
+    request: Request,
+    username: &str,
+    password: &str,
+) -> Result<(Response, Option<AuthChallenge>), RequestError> {
+    // Streamed bodies cannot be sent twice, so those only get one attempt.
+    let retry = request.try_clone();
+    let started = Instant::now();
+    let response = client.execute(request)?;
+
+    let (Some(mut retry), StatusCode::UNAUTHORIZED) = (retry, response.status()) else {
+        return Ok((response, None));
+    };
+    let Some(challenge) = parse_challenge(&response) else {
+        return Ok((response, None));
+    };
+
+    let mut cnonce = [0; 16];
+    rand_bytes(&mut cnonce)
+        .map_err(|err| RequestError::Other(format!("Digest auth failed: {err}")))?;
+    let authorization = authorization(&retry, &challenge, username, password, &hex(&cnonce))?;
+    set_header(&mut retry, AUTHORIZATION, &authorization)?;
+    let first_leg = AuthChallenge {
+        status: response.status(),
+        headers: response.headers().clone(),
+        elapsed: started.elapsed(),
+    };
+    drop(response);
+
+    Ok((client.execute(retry)?, Some(first_leg)))
+}
+
+/// The parameters of the strongest Digest challenge we support, with
+/// lowercase names.
+fn parse_challenge(response: &Response) -> Option<HashMap<String, String>> {
+    let challenges: Vec<HashMap<String, String>> = response
+        .headers()
+        .get_all(WWW_AUTHENTICATE)
+        .iter()
+        .filter_map(|value| {
+            let value = value.to_str().ok()?.trim();
+            let (scheme, params) = value.split_once(' ')?;
+            scheme
+                .eq_ignore_ascii_case("digest")
+                .then(|| parse_params(params))
+        })
+        .collect();
+
+    ALGORITHMS.iter().find_map(|algorithm| {
+        challenges
+            .iter()
+            .find(|params| {
+                params
+                    .get("algorithm")
+                    .map_or("MD5", String::as_str)
+                    .eq_ignore_ascii_case(algorithm)
+            })
+            .cloned()
+    })
+}
+
+/// Splits `name=value, name="quoted, value"` pairs.
+fn parse_params(text: &str) -> HashMap<String, String> {
+    let mut params = HashMap::new();
+    let mut chars = text.chars().peekable();
+    loop {
+        while chars.next_if(|c| *c == ',' || c.is_whitespace()).is_some() {}
+        let name: String = std::iter::from_fn(|| chars.next_if(|c| *c != '=')).collect();
+        if chars.next().is_none() {
+            break;
+        }
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => break,
+                    '\\' => value.extend(chars.next()),
+                    c => value.push(c),
+                }
+            }
+        } else {
+            value.extend(std::iter::from_fn(|| chars.next_if(|c| *c != ',')));
+        }
+        params.insert(name.trim().to_lowercase(), value.trim().to_owned());
+    }
+    params
+}
+
+/// The `Authorization` header answering `challenge`, with `cnonce` as the
+/// client nonce.
+fn authorization(
+    request: &Request,
+    challenge: &HashMap<String, String>,
+    username: &str,
+    password: &str,
+    cnonce: &str,
+) -> Result<String, RequestError> {
+    let param = |name: &str| challenge.get(name).map_or("", String::as_str);
+    let realm = param("realm");
+    let nonce = param("nonce");
+    let algorithm = challenge.get("algorithm").map_or("MD5", String::as_str);
+    let (digest, session) = match algorithm.to_uppercase().as_str() {
+        "SHA-256" => (MessageDigest::sha256(), false),
+        "SHA-256-SESS" => (MessageDigest::sha256(), true),
+        "MD5-SESS" => (MessageDigest::md5(), true),
+        _ => (MessageDigest::md5(), false),
+    };
+    let hash = |text: &str| hash_hex(digest, text.as_bytes());
+
+    let nc = "00000001";
+
+    let url = request.url();
+    let uri = match url.query() {
+        Some(query) => format!("{}?{query}", url.path()),
+        None => url.path().to_owned(),
+    };
+
+    // `auth` is preferred, `auth-int` also covers the body. Streamed bodies
+    // (files and multipart forms) cannot be hashed up front.
+    let body = match request.body() {
+        Some(body) => body.as_bytes(),
+        None => Some(&[][..]),
+    };
+    let qops: Vec<&str> = param("qop").split(',').map(str::trim).collect();
+    let qop = if qops.contains(&"auth") {
+        Some("auth")
+    } else if qops.contains(&"auth-int") {
+        if body.is_none() {
+            return Err(RequestError::Other(
+                "The server only accepts Digest auth-int, which cannot cover file or \
+                 multipart bodies"
+                    .to_owned(),
+            ));
+        }
+        Some("auth-int")
+    } else {
+        None
+    };
+
+    let mut ha1 = hash(&format!("{username}:{realm}:{password}"))?;
+    if session {
+        ha1 = hash(&format!("{ha1}:{nonce}:{cnonce}"))?;
+    }
+    let ha2 = match (qop, body) {
+        (Some("auth-int"), Some(body)) => hash(&format!(
+            "{}:{uri}:{}",
+            request.method(),
+            hash_hex(digest, body)?
+        ))?,
+        _ => hash(&format!("{}:{uri}", request.method()))?,
+    };
+    let response = match qop {
+        Some(qop) => hash(&format!("{ha1}:{nonce}:{nc}:{cnonce}:{qop}:{ha2}"))?,
+        None => hash(&format!("{ha1}:{nonce}:{ha2}"))?,
+    };
+
+    let quote = |value: &str| format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut fields = vec![
+        format!("username={}", quote(username)),
+        format!("realm={}", quote(realm)),
+        format!("nonce={}", quote(nonce)),
+        format!("uri={}", quote(&uri)),
+        format!("algorithm={algorithm}"),
+        format!("response={}", quote(&response)),
+    ];
+    if let Some(qop) = qop {
+        fields.push(format!("qop={qop}"));
+        fields.push(format!("nc={nc}"));
+        fields.push(format!("cnonce={}", quote(cnonce)));
+    }
+    if let Some(opaque) = challenge.get("opaque") {
+        fields.push(format!("opaque={}", quote(opaque)));
+    }
+    Ok(format!("Digest {}", fields.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::{blocking::Body, Method, Url};
+
+    use super::*;
+
+    /// The challenge of RFC 7616, section 3.9.1.
+    fn challenge(algorithm: &str) -> HashMap<String, String> {
+        parse_params(&format!(
+            "realm=\"http-auth@example.org\",
+             qop=\"auth, auth-int\",
+             algorithm={algorithm},
+             nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\",
+             opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\""
+        ))
+    }
+
+    fn rfc_authorization(algorithm: &str) -> String {
+        let request = Request::new(
+            Method::GET,
+            Url::parse("http://www.example.org/dir/index.html").unwrap(),
+        );
+        let cnonce = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+        match authorization(
+            &request,
+            &challenge(algorithm),
+            "Mufasa",
+            "Circle of Life",
+            cnonce,
+        ) {
+            Ok(authorization) => authorization,
+            Err(err) => panic!("digest auth failed: {err}"),
+        }
+    }
+
+    #[test]
+    fn parses_quoted_and_bare_params() {
+        let params = challenge("SHA-256");
+        assert_eq!(params.len(), 5);
+        assert_eq!(params["realm"], "http-auth@example.org");
+        assert_eq!(params["qop"], "auth, auth-int");
+        assert_eq!(params["algorithm"], "SHA-256");
+        assert_eq!(
+            params["nonce"],
+            "7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v"
+        );
+        assert_eq!(
+            params["opaque"],
+            "FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS"
+        );
+    }
+
+    #[test]
+    fn parses_escapes_and_lowercases_names() {
+        let params = parse_params(r#"Realm="say \"hi\", bye",nonce=abc"#);
+        assert_eq!(params["realm"], r#"say "hi", bye"#);
+        assert_eq!(params["nonce"], "abc");
+    }
+
+    #[test]
+    fn rfc_7616_md5() {
+        assert_eq!(
+            rfc_authorization("MD5"),
+            "Digest username=\"Mufasa\", realm=\"http-auth@example.org\", \
+             nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", uri=\"/dir/index.html\", \
+             algorithm=MD5, response=\"8ca523f5e9506fed4657c9700eebdbec\", qop=auth, \
+             nc=00000001, cnonce=\"f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ\", \
+             opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\""
+        );
+    }
+
+    #[test]
+    fn rfc_7616_sha_256() {
+        assert_eq!(
+            rfc_authorization("SHA-256"),
+            "Digest username=\"Mufasa\", realm=\"http-auth@example.org\", \
+             nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", uri=\"/dir/index.html\", \
+             algorithm=SHA-256, \
+             response=\"753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1\", \
+             qop=auth, nc=00000001, cnonce=\"f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ\", \
+             opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\""
+        );
+    }
+
+    #[test]
+    fn auth_int_needs_the_body_up_front() {
+        let challenge = parse_params(r#"realm="test", qop="auth-int", nonce="abc""#);
+        let request_with = |body: Body| {
+            let mut request = Request::new(
+                Method::POST,
+                Url::parse("http://example.org/upload").unwrap(),
+            );
+            *request.body_mut() = Some(body);
+            request
+        };
+
+        let request = request_with(Body::from("contents"));
+        match authorization(&request, &challenge, "user", "pass", "xyz") {
+            Ok(authorization) => assert!(authorization.contains("qop=auth-int")),
+            Err(err) => panic!("digest auth failed: {err}"),
+        }
+        let request = request_with(Body::new(&b"contents"[..]));
+        assert!(authorization(&request, &challenge, "user", "pass", "xyz").is_err());
+    }
+}
@@ -0,0 +1,95 @@
+use std::{collections::BTreeMap, fs, io, path::PathBuf, sync::OnceLock};
+
+use regex::Regex;
+
+/// Directory in the working directory where restui keeps its project files.
+pub const PROJECT_DIR: &str = ".restui";
+
+const ENVIRONMENTS_FILE: &str = "environments.toml";
+
+/// A named set of variables that `{{name}}` placeholders resolve against.
+pub struct Environment {
+    pub name: String,
+    pub variables: BTreeMap<String, String>,
+}
+
+pub fn environments_path() -> PathBuf {
+    PathBuf::from(PROJECT_DIR).join(ENVIRONMENTS_FILE)
+}
+
+/// Reads the environments file, where every table is one environment:
+///
+/// ```toml
+/// [dev]
+/// host = "http://localhost:8080"
+///
+/// [prod]
+/// host = "https://api.example.com"
+/// ```
+///
+/// A missing file simply means there are no environments yet.
+pub fn load_environments() -> Result<Vec<Environment>, String> {
+    let path = environments_path();
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(format!("{}: {err}", path.display())),
+    };
+
+    let environments: BTreeMap<String, BTreeMap<String, String>> =
+        toml::from_str(&text).map_err(|err| format!("{}: {err}", path.display()))?;
+
+    Ok(environments
+        .into_iter()
+        .map(|(name, variables)| Environment { name, variables })
+        .collect())
+}
+
+pub fn placeholder_regex() -> &'static Regex {
+    static PLACEHOLDER: OnceLock<Regex> = OnceLock::new();
+    PLACEHOLDER.get_or_init(|| {
+        Regex::new(r"\{\{\s*([^{}\s]+)\s*\}\}").expect("Placeholder regex is valid.")
+    })
+}
+
+/// Replaces every `{{name}}` in `text` with its value from `environment`.
+/// Names that cannot be resolved are left in place and added to `unresolved`.
+pub fn substitute(
+    text: &str,
+    environment: Option<&Environment>,
+    unresolved: &mut Vec<String>,
+) -> String {
+    placeholder_regex()
+        .replace_all(text, |captures: &regex::Captures| {
+            let name = &captures[1];
+            match environment.and_then(|environment| environment.variables.get(name)) {
+                Some(value) => value.clone(),
+                None => {
+                    if !unresolved.iter().any(|unresolved| unresolved == name) {
+                        unresolved.push(name.to_owned());
+                    }
+                    captures[0].to_owned()
+                }
+            }
+        })
+        .into_owned()
+}
+
+/// A pattern matching the placeholders in `texts` that `environment` cannot
+/// resolve, for highlighting them in the editor widgets.
+pub fn unresolved_pattern<'a>(
+    texts: impl IntoIterator<Item = &'a str>,
+    environment: Option<&Environment>,
+) -> Option<String> {
+    let mut unresolved = Vec::new();
+    for text in texts {
+        substitute(text, environment, &mut unresolved);
+    }
+
+    if unresolved.is_empty() {
+        return None;
+    }
+
+    let names: Vec<String> = unresolved.iter().map(|name| regex::escape(name)).collect();
+    Some(format!(r"\{{\{{\s*({})\s*\}}\}}", names.join("|")))
+}
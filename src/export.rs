@@ -0,0 +1,420 @@
+use std::{
+    fmt::Write as _,
+    io::{self, Write},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::{blocking::Request, header::CONTENT_TYPE, Method};
+
+use crate::request_body::{Part, PreparedBody};
+
+/// Languages and tools a request can be exported to.
+#[derive(Clone, Copy)]
+pub enum ExportFormat {
+    Curl,
+    Reqwest,
+    PythonRequests,
+    JsFetch,
+    Httpie,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 5] = [
+        ExportFormat::Curl,
+        ExportFormat::Reqwest,
+        ExportFormat::PythonRequests,
+        ExportFormat::JsFetch,
+        ExportFormat::Httpie,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ExportFormat::Curl => "curl",
+            ExportFormat::Reqwest => "Rust reqwest",
+            ExportFormat::PythonRequests => "Python requests",
+            ExportFormat::JsFetch => "JavaScript fetch",
+            ExportFormat::Httpie => "HTTPie",
+        }
+    }
+
+    pub fn file_extension(self) -> &'static str {
+        match self {
+            ExportFormat::Curl | ExportFormat::Httpie => "sh",
+            ExportFormat::Reqwest => "rs",
+            ExportFormat::PythonRequests => "py",
+            ExportFormat::JsFetch => "js",
+        }
+    }
+
+    /// `body` is what the request was built from, multipart forms and files
+    /// are written out from it.
+    pub fn render(self, request: &Request, body: Option<&PreparedBody>) -> String {
+        let snippet = Snippet::new(request, body);
+        match self {
+            ExportFormat::Curl => snippet.curl(),
+            ExportFormat::Reqwest => snippet.reqwest(),
+            ExportFormat::PythonRequests => snippet.python_requests(),
+            ExportFormat::JsFetch => snippet.js_fetch(),
+            ExportFormat::Httpie => snippet.httpie(),
+        }
+    }
+}
+
+/// The parts of a built request every format needs, as text.
+struct Snippet<'a> {
+    method: &'a Method,
+    url: &'a str,
+    headers: Vec<(&'a str, String)>,
+    body: Option<SnippetBody<'a>>,
+}
+
+/// The body as the formats write it out.
+enum SnippetBody<'a> {
+    Text(String),
+    Multipart(&'a [Part]),
+    /// The path of the file to send.
+    File(&'a str),
+}
+
+impl<'a> Snippet<'a> {
+    fn new(request: &'a Request, prepared_body: Option<&'a PreparedBody>) -> Self {
+        let multipart = matches!(prepared_body, Some(PreparedBody::Multipart(_)));
+        let headers = request
+            .headers()
+            .iter()
+            // Every tool picks a boundary of its own for a multipart form.
+            .filter(|(name, value)| {
+                !(multipart
+                    && *name == CONTENT_TYPE
+                    && value.as_bytes().starts_with(b"multipart/form-data"))
+            })
+            .map(|(name, value)| {
+                (
+                    name.as_str(),
+                    String::from_utf8_lossy(value.as_bytes()).trim().to_owned(),
+                )
+            })
+            .collect();
+        let body = match prepared_body {
+            Some(PreparedBody::Multipart(parts)) => Some(SnippetBody::Multipart(parts)),
+            Some(PreparedBody::File(path)) => Some(SnippetBody::File(path)),
+            _ => request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .filter(|bytes| !bytes.is_empty())
+                .map(|bytes| SnippetBody::Text(String::from_utf8_lossy(bytes).into_owned())),
+        };
+
+        Self {
+            method: request.method(),
+            url: request.url().as_str(),
+            headers,
+            body,
+        }
+    }
+
+    fn curl(&self) -> String {
+        let mut lines = vec![match *self.method {
+            // With a body curl would send a POST unless told otherwise.
+            Method::GET if self.body.is_none() => format!("curl {}", shell_quote(self.url)),
+            // `-X HEAD` would make curl wait for a body that never comes.
+            Method::HEAD => format!("curl --head {}", shell_quote(self.url)),
+            _ => format!("curl -X {} {}", self.method, shell_quote(self.url)),
+        }];
+        for (name, value) in &self.headers {
+            lines.push(format!("-H {}", shell_quote(&format!("{name}: {value}"))));
+        }
+        match &self.body {
+            Some(SnippetBody::Text(body)) => {
+                lines.push(format!("--data-raw {}", shell_quote(body)));
+            }
+            Some(SnippetBody::Multipart(parts)) => {
+                for part in *parts {
+                    // `--form-string` keeps a leading `@` or `<` in text as it is.
+                    lines.push(if part.file {
+                        format!(
+                            "-F {}",
+                            shell_quote(&format!("{}=@{}", part.name, part.value))
+                        )
+                    } else {
+                        format!(
+                            "--form-string {}",
+                            shell_quote(&format!("{}={}", part.name, part.value))
+                        )
+                    });
+                }
+            }
+            Some(SnippetBody::File(path)) => {
+                lines.push(format!(
+                    "--data-binary {}",
+                    shell_quote(&format!("@{path}"))
+                ));
+            }
+            None => {}
+        }
+        lines.join(" \\\n  ")
+    }
+
+    fn reqwest(&self) -> String {
+        let mut code = String::from("let client = reqwest::blocking::Client::new();\n");
+        if let Some(SnippetBody::Multipart(parts)) = &self.body {
+            code.push_str("let form = reqwest::blocking::multipart::Form::new()");
+            for part in *parts {
+                if part.file {
+                    let _ = write!(code, "\n    .file({:?}, {:?})?", part.name, part.value);
+                } else {
+                    let _ = write!(code, "\n    .text({:?}, {:?})", part.name, part.value);
+                }
+            }
+            code.push_str(";\n");
+        }
+        code.push_str("let response = client\n");
+        let method = self.method.as_str();
+        match *self.method {
+            Method::GET
+            | Method::POST
+            | Method::PUT
+            | Method::PATCH
+            | Method::DELETE
+            | Method::HEAD => {
+                let _ = writeln!(code, "    .{}({:?})", method.to_lowercase(), self.url);
+            }
+            _ => {
+                let _ = writeln!(
+                    code,
+                    "    .request(reqwest::Method::from_bytes(b{method:?})?, {:?})",
+                    self.url
+                );
+            }
+        }
+        for (name, value) in &self.headers {
+            let _ = writeln!(code, "    .header({name:?}, {value:?})");
+        }
+        match &self.body {
+            Some(SnippetBody::Text(body)) => {
+                let _ = writeln!(code, "    .body({body:?})");
+            }
+            Some(SnippetBody::Multipart(_)) => code.push_str("    .multipart(form)\n"),
+            Some(SnippetBody::File(path)) => {
+                let _ = writeln!(code, "    .body(std::fs::File::open({path:?})?)");
+            }
+            None => {}
+        }
+        code.push_str("    .send()?;\n");
+        code.push_str("println!(\"{}\", response.text()?);");
+        code
+    }
+
+    fn python_requests(&self) -> String {
+        let mut code = String::from("import requests\n\n");
+        let method = self.method.as_str();
+        match *self.method {
+            Method::GET
+            | Method::POST
+            | Method::PUT
+            | Method::PATCH
+            | Method::DELETE
+            | Method::HEAD
+            | Method::OPTIONS => {
+                let _ = writeln!(code, "response = requests.{}(", method.to_lowercase());
+            }
+            _ => {
+                code.push_str("response = requests.request(\n");
+                let _ = writeln!(code, "    {},", string_literal(method));
+            }
+        }
+        let _ = writeln!(code, "    {},", string_literal(self.url));
+        if !self.headers.is_empty() {
+            code.push_str("    headers={\n");
+            for (name, value) in &self.headers {
+                let _ = writeln!(
+                    code,
+                    "        {}: {},",
+                    string_literal(name),
+                    string_literal(value)
+                );
+            }
+            code.push_str("    },\n");
+        }
+        match &self.body {
+            Some(SnippetBody::Text(body)) => {
+                let _ = writeln!(code, "    data={},", string_literal(body));
+            }
+            // Text fields go in `files` too, or a form without files would
+            // be sent URL-encoded.
+            Some(SnippetBody::Multipart(parts)) => {
+                code.push_str("    files=[\n");
+                for part in *parts {
+                    let value = if part.file {
+                        format!("open({}, \"rb\")", string_literal(&part.value))
+                    } else {
+                        format!("(None, {})", string_literal(&part.value))
+                    };
+                    let _ = writeln!(code, "        ({}, {value}),", string_literal(&part.name));
+                }
+                code.push_str("    ],\n");
+            }
+            Some(SnippetBody::File(path)) => {
+                let _ = writeln!(code, "    data=open({}, \"rb\"),", string_literal(path));
+            }
+            None => {}
+        }
+        code.push_str(")\nprint(response.text)");
+        code
+    }
+
+    fn js_fetch(&self) -> String {
+        let mut options = Vec::new();
+        if *self.method != Method::GET {
+            options.push(format!(
+                "  method: {},\n",
+                string_literal(self.method.as_str())
+            ));
+        }
+        if !self.headers.is_empty() {
+            let mut headers = String::from("  headers: {\n");
+            for (name, value) in &self.headers {
+                let _ = writeln!(
+                    headers,
+                    "    {}: {},",
+                    string_literal(name),
+                    string_literal(value)
+                );
+            }
+            headers.push_str("  },\n");
+            options.push(headers);
+        }
+        // Files are read with Node's `openAsBlob`.
+        let mut setup = String::new();
+        match &self.body {
+            Some(SnippetBody::Text(body)) => {
+                options.push(format!("  body: {},\n", string_literal(body)));
+            }
+            Some(SnippetBody::Multipart(parts)) => {
+                if parts.iter().any(|part| part.file) {
+                    setup.push_str("import { openAsBlob } from \"node:fs\";\n\n");
+                }
+                setup.push_str("const form = new FormData();\n");
+                for part in *parts {
+                    let name = string_literal(&part.name);
+                    if part.file {
+                        let file_name = Path::new(&part.value)
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_default();
+                        let _ = writeln!(
+                            setup,
+                            "form.append({name}, await openAsBlob({}), {});",
+                            string_literal(&part.value),
+                            string_literal(&file_name)
+                        );
+                    } else {
+                        let _ = writeln!(
+                            setup,
+                            "form.append({name}, {});",
+                            string_literal(&part.value)
+                        );
+                    }
+                }
+                options.push("  body: form,\n".to_owned());
+            }
+            Some(SnippetBody::File(path)) => {
+                setup.push_str("import { openAsBlob } from \"node:fs\";\n\n");
+                options.push(format!(
+                    "  body: await openAsBlob({}),\n",
+                    string_literal(path)
+                ));
+            }
+            None => {}
+        }
+
+        let url = string_literal(self.url);
+        let call = if options.is_empty() {
+            format!("const response = await fetch({url});\n")
+        } else {
+            format!(
+                "const response = await fetch({url}, {{\n{}}});\n",
+                options.concat()
+            )
+        };
+        setup + &call + "console.log(await response.text());"
+    }
+
+    fn httpie(&self) -> String {
+        let multipart = if let Some(SnippetBody::Multipart(_)) = &self.body {
+            "--multipart "
+        } else {
+            ""
+        };
+        let mut lines = vec![format!(
+            "http {multipart}{} {}",
+            self.method,
+            shell_quote(self.url)
+        )];
+        for (name, value) in &self.headers {
+            lines.push(shell_quote(&format!("{name}:{value}")));
+        }
+        match &self.body {
+            Some(SnippetBody::Text(body)) => lines.push(format!("--raw {}", shell_quote(body))),
+            Some(SnippetBody::Multipart(parts)) => {
+                for part in *parts {
+                    let separator = if part.file { "@" } else { "=" };
+                    lines.push(shell_quote(&format!(
+                        "{}{separator}{}",
+                        part.name, part.value
+                    )));
+                }
+            }
+            // Redirected input is sent as the body.
+            Some(SnippetBody::File(path)) => lines.push(format!("< {}", shell_quote(path))),
+            None => {}
+        }
+        lines.join(" \\\n  ")
+    }
+}
+
+/// Quotes `text` as a single word for POSIX shells.
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+/// A double quoted string that Python and JavaScript both read back as `text`.
+fn string_literal(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
+}
+
+/// Copies `text` with the platform's clipboard tool, falling back to the OSC 52
+/// escape sequence, which most terminals (also over SSH) turn into a copy.
+pub fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    const CLIPBOARD_COMMANDS: &[(&str, &[&str])] = &[
+        ("pbcopy", &[]),
+        ("wl-copy", &[]),
+        ("xclip", &["-selection", "clipboard"]),
+        ("xsel", &["--clipboard", "--input"]),
+        ("clip.exe", &[]),
+    ];
+
+    for (program, args) in CLIPBOARD_COMMANDS {
+        let Ok(mut child) = Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        if child.wait()?.success() {
+            return Ok(());
+        }
+    }
+
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+    stdout.flush()
+}
@@ -0,0 +1,73 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph, Tabs},
+    Frame,
+};
+
+use crate::{centered_rect, App};
+
+pub fn render_export_popup(f: &mut Frame, app: &App) {
+    let area = centered_rect(80, 80, f.size());
+    f.render_widget(Clear, area);
+
+    let file_height = if app.export_file.is_some() { 3 } else { 0 };
+    let blocks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(file_height),
+        ])
+        .split(area);
+
+    let titles: Vec<&str> = app
+        .export_formats
+        .items
+        .iter()
+        .map(|format| format.name())
+        .collect();
+    let tabs = Tabs::new(titles)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::new().red())
+                .title("Export"),
+        )
+        .select(app.export_formats.index)
+        .highlight_style(Style::default().fg(Color::Black).bg(Color::White));
+    f.render_widget(tabs, blocks[0]);
+
+    let title = match &app.export_message {
+        Some(message) => message.clone(),
+        None => "c copy to clipboard, w write to file".to_owned(),
+    };
+    let content = match app.export_text() {
+        Ok(text) => text
+            .lines()
+            .map(|line| Line::raw(line.to_owned()))
+            .collect(),
+        Err(err) => vec![Line::styled(
+            err.to_owned(),
+            Style::default().fg(Color::Red),
+        )],
+    };
+    f.render_widget(
+        Paragraph::new(content).block(Block::default().borders(Borders::ALL).title(title)),
+        blocks[1],
+    );
+
+    if let Some(input) = &app.export_file {
+        f.set_cursor(
+            blocks[2].x + input.cursor_position() as u16 + 1,
+            blocks[2].y + 1,
+        );
+        f.render_widget(
+            input
+                .clone()
+                .block(Block::default().borders(Borders::ALL).red()),
+            blocks[2],
+        );
+    }
+}
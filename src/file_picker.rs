@@ -0,0 +1,145 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::app::StatefulList;
+
+pub struct PickerEntry {
+    label: String,
+    path: PathBuf,
+    is_dir: bool,
+}
+
+impl AsRef<str> for PickerEntry {
+    fn as_ref(&self) -> &str {
+        &self.label
+    }
+}
+
+/// Browses the file system for the file to send as the body. Paths stay
+/// relative to the directory restui runs in unless they started out absolute.
+pub struct FilePicker {
+    /// Empty for the current directory.
+    pub dir: PathBuf,
+    /// `..` first, then folders and files by name.
+    pub entries: StatefulList<PickerEntry>,
+    /// Why the directory could not be read.
+    pub error: Option<String>,
+}
+
+impl FilePicker {
+    /// Opens the directory of `path`, with its file selected if it is there.
+    pub fn open(path: &str) -> Self {
+        let path = Path::new(path);
+        let dir = match path.parent() {
+            Some(parent) if parent.is_dir() => parent.to_owned(),
+            _ => PathBuf::new(),
+        };
+        let mut picker = Self {
+            dir,
+            entries: StatefulList::with_items(Vec::new()),
+            error: None,
+        };
+        picker.read_dir();
+        if let Some(index) = picker
+            .entries
+            .items
+            .iter()
+            .position(|entry| entry.path == path)
+        {
+            picker.entries.state.select(Some(index));
+        }
+        picker
+    }
+
+    pub fn title(&self) -> String {
+        let dir = if self.dir.as_os_str().is_empty() {
+            ".".to_owned()
+        } else {
+            self.dir.display().to_string()
+        };
+        match &self.error {
+            Some(err) => format!("Select file in {dir} ({err})"),
+            None => format!("Select file in {dir}"),
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.entries.items.is_empty() {
+            self.entries.next();
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        if !self.entries.items.is_empty() {
+            self.entries.previous();
+        }
+    }
+
+    /// Opens the selected folder, or returns the selected file.
+    pub fn choose(&mut self) -> Option<PathBuf> {
+        let entry = self.entries.get()?;
+        if !entry.is_dir {
+            return Some(entry.path.clone());
+        }
+
+        // `a/b/..` is shortened to `a`, `..` is only kept above the start.
+        match self.dir.components().next_back() {
+            Some(Component::Normal(_)) if entry.label == "../" => {
+                self.dir.pop();
+            }
+            _ => self.dir = entry.path.clone(),
+        }
+        self.read_dir();
+        None
+    }
+
+    fn read_dir(&mut self) {
+        let dir = if self.dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            self.dir.as_path()
+        };
+        let mut entries: Vec<PickerEntry> = match fs::read_dir(dir) {
+            Ok(read_dir) => {
+                self.error = None;
+                read_dir
+                    .filter_map(|entry| {
+                        let entry = entry.ok()?;
+                        let name = entry.file_name().to_string_lossy().into_owned();
+                        // Follows symlinks, unlike `DirEntry::file_type`.
+                        let is_dir = entry.path().is_dir();
+                        Some(PickerEntry {
+                            label: if is_dir {
+                                format!("{name}/")
+                            } else {
+                                name.clone()
+                            },
+                            path: self.dir.join(name),
+                            is_dir,
+                        })
+                    })
+                    .collect()
+            }
+            Err(err) => {
+                self.error = Some(err.to_string());
+                Vec::new()
+            }
+        };
+        entries.sort_by_key(|entry| (!entry.is_dir, entry.label.to_lowercase()));
+
+        if self.dir.parent().is_some() || !self.dir.is_absolute() {
+            entries.insert(
+                0,
+                PickerEntry {
+                    label: "../".to_owned(),
+                    path: self.dir.join(".."),
+                    is_dir: true,
+                },
+            );
+        }
+        self.entries = StatefulList::with_items(entries);
+        self.select_next();
+    }
+}
@@ -0,0 +1,209 @@
+use serde_json::Value;
+
+/// One step of a filter path like `.data.items[].id`.
+enum Segment {
+    Key(String),
+    Index(i64),
+    Slice(Option<i64>, Option<i64>),
+    Iterate,
+}
+
+/// Applies a jq-like path expression to `value`. Both jq syntax
+/// (`.data.items[].id`, `.["a key"]`, `.[1:3]`) and the JSONPath equivalents
+/// (`$.data.items[*].id`) are accepted. An expression can produce several
+/// results, just like in jq.
+pub fn apply(expression: &str, value: &Value) -> Result<Vec<Value>, String> {
+    let segments = parse(expression)?;
+
+    let mut values = vec![value.clone()];
+    for segment in &segments {
+        let mut next_values = Vec::new();
+        for value in values {
+            select(segment, value, &mut next_values)?;
+        }
+        values = next_values;
+    }
+
+    Ok(values)
+}
+
+fn select(segment: &Segment, value: Value, results: &mut Vec<Value>) -> Result<(), String> {
+    match (segment, value) {
+        (Segment::Key(key), Value::Object(mut map)) => {
+            results.push(map.remove(key).unwrap_or(Value::Null))
+        }
+        (Segment::Key(_), Value::Null) => results.push(Value::Null),
+        (Segment::Key(key), value) => {
+            return Err(format!("cannot index {} with \"{key}\"", type_name(&value)))
+        }
+        (Segment::Index(index), Value::Array(mut array)) => {
+            let len = array.len() as i64;
+            let index = if *index < 0 { len + index } else { *index };
+            if (0..len).contains(&index) {
+                results.push(array.swap_remove(index as usize));
+            } else {
+                results.push(Value::Null);
+            }
+        }
+        (Segment::Index(_) | Segment::Slice(..), Value::Null) => results.push(Value::Null),
+        (Segment::Index(index), value) => {
+            return Err(format!("cannot index {} with {index}", type_name(&value)))
+        }
+        (Segment::Slice(start, end), Value::Array(array)) => {
+            let len = array.len() as i64;
+            let clamp = |bound: i64| {
+                let bound = if bound < 0 { len + bound } else { bound };
+                bound.clamp(0, len) as usize
+            };
+            let start = start.map_or(0, clamp);
+            let end = end.map_or(array.len(), clamp);
+            let slice = if start < end {
+                array[start..end].to_vec()
+            } else {
+                Vec::new()
+            };
+            results.push(Value::Array(slice));
+        }
+        (Segment::Slice(..), value) => return Err(format!("cannot slice {}", type_name(&value))),
+        (Segment::Iterate, Value::Array(array)) => results.extend(array),
+        (Segment::Iterate, Value::Object(map)) => results.extend(map.into_iter().map(|(_, v)| v)),
+        (Segment::Iterate, value) => {
+            return Err(format!("cannot iterate over {}", type_name(&value)))
+        }
+    }
+
+    Ok(())
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn parse(expression: &str) -> Result<Vec<Segment>, String> {
+    let chars: Vec<char> = expression.trim().chars().collect();
+    let mut segments = Vec::new();
+    let mut position = 0;
+
+    if chars.first() == Some(&'$') {
+        position += 1;
+    } else if chars.first() != Some(&'.') {
+        return Err("filters start with '.' or '$'".to_owned());
+    }
+
+    while position < chars.len() {
+        match chars[position] {
+            '.' => {
+                position += 1;
+                match chars.get(position) {
+                    // `.[...]` is the same as `[...]`.
+                    Some('[') | None => {}
+                    Some('*') => {
+                        position += 1;
+                        segments.push(Segment::Iterate);
+                    }
+                    Some('"') => {
+                        let (key, end) = parse_string(&chars, position)?;
+                        position = end;
+                        segments.push(Segment::Key(key));
+                    }
+                    Some(c) if is_identifier_char(*c) => {
+                        let start = position;
+                        while position < chars.len() && is_identifier_char(chars[position]) {
+                            position += 1;
+                        }
+                        segments.push(Segment::Key(chars[start..position].iter().collect()));
+                    }
+                    Some(c) => return Err(format!("unexpected '{c}' at {}", position + 1)),
+                }
+            }
+            '[' => {
+                let (segment, end) = parse_brackets(&chars, position)?;
+                position = end;
+                segments.push(segment);
+            }
+            c => return Err(format!("unexpected '{c}' at {}", position + 1)),
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Parses a `[...]` selector starting at `start`, returning the segment and
+/// the position after the closing bracket.
+fn parse_brackets(chars: &[char], start: usize) -> Result<(Segment, usize), String> {
+    let mut position = start + 1;
+
+    if chars.get(position) == Some(&'"') {
+        let (key, end) = parse_string(chars, position)?;
+        return match chars.get(end) {
+            Some(']') => Ok((Segment::Key(key), end + 1)),
+            _ => Err(format!("expected ']' at {}", end + 1)),
+        };
+    }
+
+    let content_start = position;
+    while position < chars.len() && chars[position] != ']' {
+        position += 1;
+    }
+    if position == chars.len() {
+        return Err(format!("unclosed '[' at {}", start + 1));
+    }
+
+    let content: String = chars[content_start..position].iter().collect();
+    let content = content.trim();
+    let parse_index = |text: &str| {
+        text.trim()
+            .parse::<i64>()
+            .map_err(|_| format!("invalid index '{}' at {}", text.trim(), content_start + 1))
+    };
+    let parse_bound = |text: &str| {
+        if text.trim().is_empty() {
+            Ok(None)
+        } else {
+            parse_index(text).map(Some)
+        }
+    };
+
+    let segment = if content.is_empty() || content == "*" {
+        Segment::Iterate
+    } else if let Some((from, to)) = content.split_once(':') {
+        Segment::Slice(parse_bound(from)?, parse_bound(to)?)
+    } else {
+        Segment::Index(parse_index(content)?)
+    };
+
+    Ok((segment, position + 1))
+}
+
+/// Parses a double-quoted key starting at `start`, returning the key and the
+/// position after the closing quote.
+fn parse_string(chars: &[char], start: usize) -> Result<(String, usize), String> {
+    let mut key = String::new();
+    let mut position = start + 1;
+    while let Some(c) = chars.get(position) {
+        match c {
+            '"' => return Ok((key, position + 1)),
+            '\\' => {
+                position += 1;
+                if let Some(escaped) = chars.get(position) {
+                    key.push(*escaped);
+                }
+            }
+            c => key.push(*c),
+        }
+        position += 1;
+    }
+
+    Err(format!("unclosed '\"' at {}", start + 1))
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
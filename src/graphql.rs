@@ -0,0 +1,534 @@
+use std::{
+    collections::HashMap,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use serde_json::{Map, Value};
+
+use crate::request::{self, PreparedRequest};
+
+/// Asks the server for its types and their fields, which is all completion
+/// needs. Wrapped types are unwrapped four levels deep, enough for `[[T!]!]!`.
+pub const INTROSPECTION_QUERY: &str = "query IntrospectionQuery {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    subscriptionType { name }
+    types {
+      name
+      fields(includeDeprecated: true) {
+        name
+        type { ...TypeRef }
+      }
+    }
+  }
+}
+
+fragment TypeRef on __Type {
+  kind
+  name
+  ofType { kind name ofType { kind name ofType { kind name ofType { kind name } } } }
+}";
+
+/// Wraps the query into the JSON body GraphQL servers expect. Blank variables
+/// are sent as an empty object.
+pub fn request_body(
+    query: &str,
+    variables: &str,
+    operation_name: Option<String>,
+) -> Result<String, String> {
+    let variables = if variables.trim().is_empty() {
+        Value::Object(Map::new())
+    } else {
+        match serde_json::from_str(variables) {
+            Ok(value @ Value::Object(_)) => value,
+            Ok(_) => return Err("The GraphQL variables must be a JSON object".to_owned()),
+            Err(err) => return Err(format!("The GraphQL variables are not valid JSON: {err}")),
+        }
+    };
+
+    let mut body = Map::new();
+    body.insert("query".to_owned(), Value::String(query.to_owned()));
+    body.insert("variables".to_owned(), variables);
+    body.insert(
+        "operationName".to_owned(),
+        operation_name.map_or(Value::Null, Value::String),
+    );
+    Ok(Value::Object(body).to_string())
+}
+
+/// Splits a `{"query", "variables", "operationName"}` body back into the
+/// query and the pretty-printed variables, for imported requests.
+pub fn parse_request_body(body: &str) -> Option<(String, String)> {
+    let Ok(Value::Object(body)) = serde_json::from_str(body) else {
+        return None;
+    };
+    if body
+        .keys()
+        .any(|key| !matches!(key.as_str(), "query" | "variables" | "operationName"))
+    {
+        return None;
+    }
+
+    let query = body.get("query")?.as_str()?.to_owned();
+    let variables = match body.get("variables") {
+        Some(variables @ Value::Object(fields)) if !fields.is_empty() => {
+            serde_json::to_string_pretty(variables).ok()?
+        }
+        _ => String::new(),
+    };
+    Some((query, variables))
+}
+
+/// The name of the operation to run: the only one in the document, or the one
+/// the cursor is in. Anonymous operations have no name to send.
+pub fn operation_name(query: &str, cursor: usize) -> Option<String> {
+    let (tokens, _) = tokenize(query);
+    let mut operations = Vec::new();
+    let mut depth = 0;
+    let mut start = None;
+
+    for (index, &(offset, token)) in tokens.iter().enumerate() {
+        match token {
+            Token::Name(word) if depth == 0 && start.is_none() => {
+                let name = match tokens.get(index + 1) {
+                    Some((_, Token::Name(name))) => Some(name.to_string()),
+                    _ => None,
+                };
+                // Fragments are not operations, but their braces still count.
+                start = Some((offset, (word != "fragment").then_some(name)));
+            }
+            Token::Punct('{') => {
+                if depth == 0 && start.is_none() {
+                    // An anonymous query, written as just a selection set.
+                    start = Some((offset, Some(None)));
+                }
+                depth += 1;
+            }
+            Token::Punct('}') if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    if let Some((start, Some(name))) = start.take() {
+                        operations.push((start..offset + 1, name));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    // Still being written.
+    if let Some((start, Some(name))) = start {
+        operations.push((start..query.len(), name));
+    }
+
+    match operations.as_slice() {
+        [(_, name)] => name.clone(),
+        _ => operations
+            .into_iter()
+            .find(|(range, _)| range.contains(&cursor) || range.end == cursor)
+            .and_then(|(_, name)| name),
+    }
+}
+
+/// The `errors` of a GraphQL response, each with where in the query it
+/// happened and the path of the field it belongs to.
+pub fn errors(json: &Value) -> Vec<String> {
+    let Some(errors) = json.get("errors").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+
+    errors
+        .iter()
+        .map(|error| {
+            let mut text = match error.get("message") {
+                Some(Value::String(message)) => message.clone(),
+                _ => error.to_string(),
+            };
+            let location = error
+                .get("locations")
+                .and_then(|locations| locations.get(0));
+            if let Some(location) = location {
+                let line = location.get("line").and_then(Value::as_u64).unwrap_or(0);
+                let column = location.get("column").and_then(Value::as_u64).unwrap_or(0);
+                text.push_str(&format!(" (line {line}, column {column})"));
+            }
+            if let Some(Value::Array(path)) = error.get("path") {
+                let path: Vec<String> = path
+                    .iter()
+                    .map(|segment| match segment {
+                        Value::String(name) => name.clone(),
+                        other => other.to_string(),
+                    })
+                    .collect();
+                text.push_str(&format!(" at {}", path.join(".")));
+            }
+            text
+        })
+        .collect()
+}
+
+pub struct Field {
+    pub name: String,
+    /// The type as written in the schema, like `[User!]!`.
+    pub signature: String,
+    /// The named type without list and non-null wrappers, whose fields can be
+    /// selected below this one.
+    type_name: String,
+}
+
+/// The types and fields of a GraphQL endpoint, from an introspection query.
+pub struct Schema {
+    /// The URL the schema was fetched from.
+    pub url: String,
+    query_type: Option<String>,
+    mutation_type: Option<String>,
+    subscription_type: Option<String>,
+    types: HashMap<String, Vec<Field>>,
+}
+
+impl Schema {
+    /// Reads the answer to the introspection query.
+    pub fn from_response(url: String, response: &request::Response) -> Result<Self, String> {
+        let Some(json) = &response.json else {
+            return Err(format!(
+                "the server answered {} without JSON",
+                response.status
+            ));
+        };
+        let Some(schema) = json.pointer("/data/__schema") else {
+            return Err(match errors(json).into_iter().next() {
+                Some(error) => error,
+                None => format!("the server answered {} without a schema", response.status),
+            });
+        };
+
+        let root_type = |name: &str| {
+            schema
+                .get(name)
+                .and_then(|root| root.get("name"))
+                .and_then(Value::as_str)
+                .map(str::to_owned)
+        };
+        let types = schema
+            .get("types")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|schema_type| {
+                let name = schema_type.get("name")?.as_str()?.to_owned();
+                // Unions and scalars have no fields, but any type has `__typename`.
+                let mut fields: Vec<Field> = schema_type
+                    .get("fields")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|field| {
+                        let (type_name, signature) = type_ref(field.get("type")?)?;
+                        Some(Field {
+                            name: field.get("name")?.as_str()?.to_owned(),
+                            signature,
+                            type_name,
+                        })
+                    })
+                    .collect();
+                fields.push(Field {
+                    name: "__typename".to_owned(),
+                    signature: "String!".to_owned(),
+                    type_name: "String".to_owned(),
+                });
+                Some((name, fields))
+            })
+            .collect();
+
+        Ok(Self {
+            url,
+            query_type: root_type("queryType"),
+            mutation_type: root_type("mutationType"),
+            subscription_type: root_type("subscriptionType"),
+            types,
+        })
+    }
+
+    pub fn type_count(&self) -> usize {
+        self.types.len()
+    }
+
+    fn field_type(&self, type_name: &str, field: &str) -> Option<String> {
+        self.types
+            .get(type_name)?
+            .iter()
+            .find(|candidate| candidate.name == field)
+            .map(|field| field.type_name.clone())
+    }
+}
+
+/// The named type and the signature of an introspected type reference.
+fn type_ref(value: &Value) -> Option<(String, String)> {
+    match value.get("kind")?.as_str()? {
+        "NON_NULL" => {
+            let (name, signature) = type_ref(value.get("ofType")?)?;
+            Some((name, format!("{signature}!")))
+        }
+        "LIST" => {
+            let (name, signature) = type_ref(value.get("ofType")?)?;
+            Some((name, format!("[{signature}]")))
+        }
+        _ => {
+            let name = value.get("name")?.as_str()?.to_owned();
+            Some((name.clone(), name))
+        }
+    }
+}
+
+/// The fields that can be selected where the cursor is.
+pub struct Completion<'a> {
+    /// The part of the field name in front of the cursor.
+    pub prefix: String,
+    pub fields: Vec<&'a Field>,
+}
+
+impl Completion<'_> {
+    /// What the prefix can be extended by without ruling out a field.
+    pub fn common_suffix(&self) -> &str {
+        let Some((first, rest)) = self.fields.split_first() else {
+            return "";
+        };
+        let mut common = first.name.as_str();
+        for field in rest {
+            let length = common
+                .char_indices()
+                .zip(field.name.chars())
+                .find(|((_, a), b)| a != b)
+                .map_or(common.len().min(field.name.len()), |((index, _), _)| index);
+            common = &common[..length];
+        }
+        common.get(self.prefix.len()..).unwrap_or_default()
+    }
+}
+
+/// Completes the field name in front of `cursor`, a byte offset into `query`.
+/// None where no field goes, like in arguments or after `...`.
+pub fn complete<'a>(schema: &'a Schema, query: &str, cursor: usize) -> Option<Completion<'a>> {
+    let before = query.get(..cursor)?;
+    let prefix_start = before
+        .trim_end_matches(|c: char| c.is_ascii_alphanumeric() || c == '_')
+        .len();
+    let (tokens, in_string_or_comment) = tokenize(&before[..prefix_start]);
+    if in_string_or_comment {
+        return None;
+    }
+    if let Some((_, Token::Spread | Token::Punct('@' | '$') | Token::Name("on"))) = tokens.last() {
+        return None;
+    }
+
+    let type_name = selection_type(schema, &tokens)?;
+    let prefix = &before[prefix_start..];
+    Some(Completion {
+        prefix: prefix.to_owned(),
+        fields: schema
+            .types
+            .get(&type_name)?
+            .iter()
+            .filter(|field| field.name.starts_with(prefix))
+            .collect(),
+    })
+}
+
+/// The type whose fields are selected at the end of `tokens`, following the
+/// selection sets down from the operation or fragment they are in.
+fn selection_type(schema: &Schema, tokens: &[(usize, Token)]) -> Option<String> {
+    let mut stack: Vec<Option<String>> = Vec::new();
+    // The type of the selection set opened by the next `{`, if it is not the
+    // type of the last field.
+    let mut next_type = None;
+    let mut last_field = None;
+    let mut parentheses = 0;
+
+    let mut index = 0;
+    while index < tokens.len() {
+        let token = tokens[index].1;
+        let current = index;
+        let next = move |offset: usize| tokens.get(current + 1 + offset).map(|(_, token)| *token);
+        index += 1;
+
+        // Arguments and variable definitions hold no fields.
+        if parentheses > 0 {
+            match token {
+                Token::Punct('(') => parentheses += 1,
+                Token::Punct(')') => parentheses -= 1,
+                _ => {}
+            }
+            continue;
+        }
+
+        match token {
+            Token::Punct('(') => parentheses += 1,
+            Token::Punct('{') => {
+                let selection_type = match next_type.take() {
+                    Some(selection_type) => selection_type,
+                    None if stack.is_empty() => schema.query_type.clone(),
+                    None => stack
+                        .last()
+                        .cloned()
+                        .flatten()
+                        .zip(last_field)
+                        .and_then(|(parent, field)| schema.field_type(&parent, field)),
+                };
+                stack.push(selection_type);
+                last_field = None;
+            }
+            Token::Punct('}') => {
+                stack.pop();
+                last_field = None;
+            }
+            // Directive and variable names.
+            Token::Punct('@' | '$') => index += 1,
+            Token::Spread => match (next(0), next(1)) {
+                (Some(Token::Name("on")), Some(Token::Name(type_name))) => {
+                    next_type = Some(Some(type_name.to_owned()));
+                    index += 2;
+                }
+                // A fragment spread.
+                (Some(Token::Name(_)), _) => index += 1,
+                // An inline fragment without a type condition.
+                _ => next_type = Some(stack.last().cloned().flatten()),
+            },
+            Token::Name(word) if stack.is_empty() => match word {
+                "query" => next_type = Some(schema.query_type.clone()),
+                "mutation" => next_type = Some(schema.mutation_type.clone()),
+                "subscription" => next_type = Some(schema.subscription_type.clone()),
+                "fragment" => {
+                    if let (Some(Token::Name("on")), Some(Token::Name(type_name))) =
+                        (next(1), next(2))
+                    {
+                        next_type = Some(Some(type_name.to_owned()));
+                        index += 3;
+                    }
+                }
+                // The operation name.
+                _ => {}
+            },
+            // With an alias the field name comes after the colon.
+            Token::Name(word) => last_field = Some(word),
+            _ => {}
+        }
+    }
+
+    if parentheses > 0 {
+        return None;
+    }
+    stack.pop()?
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Token<'a> {
+    /// Names, keywords and numbers.
+    Name(&'a str),
+    Punct(char),
+    /// `...`
+    Spread,
+    String,
+}
+
+/// Splits a GraphQL document into tokens with their byte offsets, skipping
+/// whitespace, commas and comments. Also tells whether it ends inside a
+/// string or a comment.
+fn tokenize(text: &str) -> (Vec<(usize, Token<'_>)>, bool) {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '#' => {
+                if !chars.any(|(_, c)| c == '\n') {
+                    return (tokens, true);
+                }
+            }
+            '"' => {
+                let block = text[start..].starts_with("\"\"\"");
+                let end = if block {
+                    text[start + 3..]
+                        .find("\"\"\"")
+                        .map(|end| start + 3 + end + 3)
+                } else {
+                    string_end(text, start + 1)
+                };
+                let Some(end) = end else {
+                    return (tokens, true);
+                };
+                tokens.push((start, Token::String));
+                while chars.next_if(|(index, _)| *index < end).is_some() {}
+            }
+            '.' if text[start..].starts_with("...") => {
+                tokens.push((start, Token::Spread));
+                chars.next();
+                chars.next();
+            }
+            c if c.is_ascii_alphanumeric() || c == '_' || c == '-' => {
+                let mut end = start + c.len_utf8();
+                while let Some((index, c)) =
+                    chars.next_if(|(_, c)| c.is_ascii_alphanumeric() || *c == '_')
+                {
+                    end = index + c.len_utf8();
+                }
+                tokens.push((start, Token::Name(&text[start..end])));
+            }
+            c if c.is_whitespace() || c == ',' => {}
+            c => tokens.push((start, Token::Punct(c))),
+        }
+    }
+    (tokens, false)
+}
+
+/// The offset just past the quote closing the string that starts at `from`.
+fn string_end(text: &str, from: usize) -> Option<usize> {
+    let mut escaped = false;
+    for (index, c) in text[from..].char_indices() {
+        match c {
+            '\n' => return None,
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return Some(from + index + 1),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+/// Where the schema for completion stands.
+pub enum SchemaState {
+    None,
+    Loading(PendingSchema),
+    Loaded(Schema),
+    Failed(String),
+}
+
+/// An introspection query running on a worker thread, like a sent request.
+pub struct PendingSchema {
+    receiver: Receiver<Result<Schema, String>>,
+}
+
+impl PendingSchema {
+    pub fn spawn(request: PreparedRequest) -> Self {
+        let url = request.url.clone();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let result = request::send_request(request)
+                .map_err(|err| err.to_string())
+                .and_then(|response| Schema::from_response(url, &response));
+            let _ = sender.send(result);
+        });
+        Self { receiver }
+    }
+
+    /// Returns the schema once the worker has finished, without blocking.
+    pub fn poll(&self) -> Option<Result<Schema, String>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(
+                "the introspection worker stopped unexpectedly".to_owned(),
+            )),
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Style, Stylize},
+    style::{Color, Style, Stylize},
     widgets::{Block, Borders, Paragraph},
     Frame,
 };
@@ -8,16 +8,20 @@ use ratatui::{
 use crate::{App, SelectionState, app::is_within_block};
 
 pub fn render_header(f: &mut Frame, app: &mut App, chunk: &Rect) {
+    // at least 7 chars so the standard methods line up, and 2 for border
+    let request_type_width = app.selected_request_type.as_str().len().clamp(7, 20) as u16 + 2;
     let blocks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
-            Constraint::Length(9), // space for 7 chars and 2 for border
+            Constraint::Length(request_type_width),
             Constraint::Min(0),
+            Constraint::Length(20),
         ])
         .split(*chunk);
 
     let request_type_block = blocks[0];
     let url_block = blocks[1];
+    let environment_block = blocks[2];
 
     if let Some(position) = app.last_mouse_down_event {
         if is_within_block(&request_type_block, position) {
@@ -26,9 +30,18 @@ pub fn render_header(f: &mut Frame, app: &mut App, chunk: &Rect) {
         } else if is_within_block(&url_block, position) {
             app.selected_input = SelectionState::Url;
             app.last_mouse_down_event = None;
+        } else if is_within_block(&environment_block, position) {
+            app.last_mouse_down_event = None;
+            app.open_environment_popup();
         }
     }
 
+    let environment_name = app
+        .active_environment()
+        .map_or("No environment", |environment| environment.name.as_str());
+    let environment_paragraph = Paragraph::new(environment_name)
+        .block(Block::default().borders(Borders::ALL).title("Environment"));
+
     let mut request_type_paragraph = Paragraph::new(app.selected_request_type.as_str())
         .block(Block::default().borders(Borders::ALL));
 
@@ -36,7 +49,11 @@ pub fn render_header(f: &mut Frame, app: &mut App, chunk: &Rect) {
         .widgets
         .url_text_input
         .clone()
-        .block(Block::default().borders(Borders::ALL));
+        .block(Block::default().borders(Borders::ALL))
+        .highlight(
+            app.unresolved_pattern.clone(),
+            Style::default().fg(Color::White).bg(Color::Red),
+        );
 
     let selected_block_style = Block::default()
         .borders(Borders::ALL)
@@ -58,4 +75,5 @@ pub fn render_header(f: &mut Frame, app: &mut App, chunk: &Rect) {
     }
     f.render_widget(request_type_paragraph, request_type_block);
     f.render_widget(text_input, url_block);
+    f.render_widget(environment_paragraph, environment_block);
 }
@@ -0,0 +1,181 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    StatusCode, Version,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{collection::SavedRequest, environment::PROJECT_DIR, request::Response};
+
+const HISTORY_FILE: &str = "history.jsonl";
+/// The oldest entries are dropped once there are more.
+const MAX_ENTRIES: usize = 200;
+/// Longer response bodies are cut off in the history, so one large download
+/// does not bloat the file.
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// A sent request and what came back, one JSON object per line in the history
+/// file.
+#[derive(Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub timestamp: DateTime<Utc>,
+    /// Method and URL as sent, with variables resolved.
+    pub method: String,
+    pub url: String,
+    pub status: Option<u16>,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+    /// The editor contents the request was sent from.
+    pub request: SavedRequest,
+    pub response: Option<HistoryResponse>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct HistoryResponse {
+    pub status: u16,
+    pub version: String,
+    pub headers: Vec<(String, String)>,
+    /// At most `MAX_BODY_BYTES` of it.
+    pub body: String,
+    /// Of the whole body, as received.
+    pub size: usize,
+}
+
+impl HistoryEntry {
+    /// Whether the entry matches the history filter text.
+    pub fn matches(&self, filter: &str) -> bool {
+        let filter = filter.to_lowercase();
+        let status = self
+            .status
+            .map(|status| status.to_string())
+            .unwrap_or_default();
+        [self.method.as_str(), self.url.as_str(), status.as_str()]
+            .iter()
+            .any(|field| field.to_lowercase().contains(&filter))
+    }
+}
+
+impl From<&Response> for HistoryResponse {
+    fn from(response: &Response) -> Self {
+        Self {
+            status: response.status.as_u16(),
+            version: format!("{:?}", response.version),
+            headers: response
+                .headers
+                .iter()
+                .map(|(name, value)| {
+                    (
+                        name.to_string(),
+                        String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                    )
+                })
+                .collect(),
+            body: truncate(&response.body, MAX_BODY_BYTES).to_owned(),
+            size: response.size,
+        }
+    }
+}
+
+impl HistoryResponse {
+    pub fn to_response(&self, duration_ms: u64) -> Response {
+        let version = match self.version.as_str() {
+            "HTTP/0.9" => Version::HTTP_09,
+            "HTTP/1.0" => Version::HTTP_10,
+            "HTTP/2.0" => Version::HTTP_2,
+            "HTTP/3.0" => Version::HTTP_3,
+            _ => Version::HTTP_11,
+        };
+        let headers = self
+            .headers
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((
+                    HeaderName::from_bytes(name.as_bytes()).ok()?,
+                    HeaderValue::from_str(value).ok()?,
+                ))
+            })
+            .collect::<HeaderMap>();
+
+        Response::new(
+            StatusCode::from_u16(self.status).unwrap_or(StatusCode::OK),
+            version,
+            headers,
+            self.body.clone(),
+            self.size,
+            Duration::from_millis(duration_ms),
+        )
+    }
+}
+
+/// The longest prefix of `text` that fits in `max_bytes`, without splitting a
+/// character.
+fn truncate(text: &str, max_bytes: usize) -> &str {
+    let mut end = max_bytes.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+pub fn history_path() -> PathBuf {
+    PathBuf::from(PROJECT_DIR).join(HISTORY_FILE)
+}
+
+/// Reads the history file, newest entries first. Lines that cannot be parsed
+/// are skipped rather than hiding the whole history.
+pub fn load_history() -> Result<Vec<HistoryEntry>, String> {
+    let path = history_path();
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(format!("{}: {err}", path.display())),
+    };
+
+    Ok(text
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Adds an entry at the end of the history file, dropping the oldest ones
+/// beyond `MAX_ENTRIES`.
+pub fn append_history(entry: &HistoryEntry) -> Result<(), String> {
+    let path = history_path();
+    let write = || -> io::Result<()> {
+        fs::create_dir_all(PROJECT_DIR)?;
+        ignore_history()?;
+        let line = serde_json::to_string(entry)?;
+
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+        let lines: Vec<&str> = text.lines().collect();
+        if lines.len() < MAX_ENTRIES {
+            let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+            return writeln!(file, "{line}");
+        }
+        let kept = &lines[lines.len() + 1 - MAX_ENTRIES..];
+        fs::write(&path, format!("{}\n{line}\n", kept.join("\n")))
+    };
+    write().map_err(|err| format!("{}: {err}", path.display()))
+}
+
+/// `.restui` is meant to be committed, but the history holds responses and is
+/// kept out of git with a `.gitignore` of its own, unless there is one already.
+fn ignore_history() -> io::Result<()> {
+    let path = PathBuf::from(PROJECT_DIR).join(".gitignore");
+    if path.exists() {
+        return Ok(());
+    }
+    fs::write(path, format!("{HISTORY_FILE}\n"))
+}
@@ -0,0 +1,114 @@
+use chrono::Local;
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::{centered_rect, history::HistoryEntry, json, App};
+
+pub fn render_history_popup(f: &mut Frame, app: &mut App) {
+    let area = centered_rect(80, 80, f.size());
+    f.render_widget(Clear, area);
+
+    let blocks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(area);
+    let filter_block = blocks[0];
+    let content_blocks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+        .split(blocks[1]);
+
+    let title = match &app.history_error {
+        Some(err) => format!("History ({err})"),
+        None => "History".to_owned(),
+    };
+    f.set_cursor(
+        filter_block.x + app.history_filter.cursor_position() as u16 + 1,
+        filter_block.y + 1,
+    );
+    f.render_widget(
+        app.history_filter.clone().block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::new().red())
+                .title(title),
+        ),
+        filter_block,
+    );
+
+    let entries = app.filtered_history();
+    let items: Vec<ListItem> = entries.iter().map(|entry| history_item(entry)).collect();
+    let detail = app
+        .history_state
+        .selected()
+        .and_then(|index| entries.get(index))
+        .map(|entry| history_detail(entry))
+        .unwrap_or_default();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL))
+        .highlight_style(
+            Style::default()
+                .bg(Color::White)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+        );
+    f.render_stateful_widget(list, content_blocks[0], &mut app.history_state);
+
+    f.render_widget(
+        Paragraph::new(detail).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Enter to restore"),
+        ),
+        content_blocks[1],
+    );
+}
+
+fn status_span(entry: &HistoryEntry) -> Span<'static> {
+    match entry.status {
+        Some(status) if status < 400 => Span::raw(status.to_string()).fg(Color::Green),
+        Some(status) => Span::raw(status.to_string()).fg(Color::Red),
+        None => Span::raw("ERR").fg(Color::Red),
+    }
+}
+
+fn history_item(entry: &HistoryEntry) -> ListItem<'static> {
+    let timestamp = entry.timestamp.with_timezone(&Local);
+    ListItem::new(Line::from(vec![
+        Span::raw(timestamp.format("%Y-%m-%d %H:%M:%S ").to_string()).fg(Color::DarkGray),
+        Span::raw(format!("{:<7} ", entry.method)).bold(),
+        status_span(entry),
+        Span::raw(format!(" {:>6} ms ", entry.duration_ms)).fg(Color::DarkGray),
+        Span::raw(entry.url.clone()),
+    ]))
+}
+
+fn history_detail(entry: &HistoryEntry) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::from(vec![
+            Span::raw(format!("{} ", entry.method)).bold(),
+            Span::raw(entry.url.clone()),
+        ]),
+        Line::from(vec![
+            status_span(entry),
+            Span::raw(format!("  {} ms", entry.duration_ms)),
+        ]),
+        Line::raw(""),
+    ];
+
+    if let Some(err) = &entry.error {
+        lines.push(Line::styled(err.clone(), Style::default().fg(Color::Red)));
+    }
+    if let Some(response) = &entry.response {
+        let body = json::pretty_print(&response.body);
+        lines.extend(body.lines().map(|line| Line::raw(line.to_owned())));
+    }
+
+    lines
+}
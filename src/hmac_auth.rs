@@ -0,0 +1,152 @@
+use std::sync::OnceLock;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use openssl::hash::MessageDigest;
+use regex::bytes::Regex;
+use reqwest::{blocking::Request, header::HeaderName};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::set_header,
+    crypto::{hex, hmac},
+    request::RequestError,
+};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HmacAlgorithm {
+    #[default]
+    Sha256,
+    Sha1,
+    Sha512,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+/// A signature over parts of the request, as partner webhooks and many
+/// internal APIs use. `string_to_sign` and `header_value` are templates, see
+/// [`render`] for the placeholders.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct HmacConfig {
+    pub algorithm: HmacAlgorithm,
+    pub secret: String,
+    pub encoding: SignatureEncoding,
+    pub string_to_sign: String,
+    pub header: String,
+    pub header_value: String,
+    /// Also sends the timestamp in this header, when set.
+    pub timestamp_header: String,
+}
+
+impl Default for HmacConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: HmacAlgorithm::default(),
+            secret: String::new(),
+            encoding: SignatureEncoding::default(),
+            string_to_sign: "{timestamp}.{body}".to_owned(),
+            header: "X-Signature".to_owned(),
+            header_value: "{signature}".to_owned(),
+            timestamp_header: String::new(),
+        }
+    }
+}
+
+/// Signs a built request and adds the signature header. The timestamp is
+/// taken once, so the signed string and the headers agree on it.
+pub fn sign(
+    request: &mut Request,
+    config: &HmacConfig,
+    now: DateTime<Utc>,
+) -> Result<(), RequestError> {
+    let header = parse_header_name(&config.header)?;
+    let timestamp = now.timestamp().to_string();
+
+    let digest = match config.algorithm {
+        HmacAlgorithm::Sha256 => MessageDigest::sha256(),
+        HmacAlgorithm::Sha1 => MessageDigest::sha1(),
+        HmacAlgorithm::Sha512 => MessageDigest::sha512(),
+    };
+    let message = render(&config.string_to_sign, request, now, None)?;
+    let mac = hmac(digest, config.secret.as_bytes(), &message)?;
+    let signature = match config.encoding {
+        SignatureEncoding::Hex => hex(&mac),
+        SignatureEncoding::Base64 => STANDARD.encode(mac),
+    };
+
+    let value = render(&config.header_value, request, now, Some(&signature))?;
+    set_header(request, header, &String::from_utf8_lossy(&value))?;
+    if !config.timestamp_header.trim().is_empty() {
+        let timestamp_header = parse_header_name(&config.timestamp_header)?;
+        set_header(request, timestamp_header, &timestamp)?;
+    }
+    Ok(())
+}
+
+fn parse_header_name(name: &str) -> Result<HeaderName, RequestError> {
+    HeaderName::from_bytes(name.trim().as_bytes())
+        .map_err(|_| RequestError::Other(format!("'{name}' is not a valid header name")))
+}
+
+fn template_regex() -> &'static Regex {
+    static TEMPLATE: OnceLock<Regex> = OnceLock::new();
+    TEMPLATE.get_or_init(|| Regex::new(r"\{([a-z_]+)\}|\\n").expect("Template regex is valid."))
+}
+
+/// Fills in a template. Known placeholders are `{method}`, `{url}`, `{host}`,
+/// `{path}`, `{query}`, `{body}`, `{timestamp}` (Unix seconds),
+/// `{timestamp_ms}` and, in the header value, `{signature}`. `\n` stands for
+/// a line break, others are left as they are.
+fn render(
+    template: &str,
+    request: &Request,
+    now: DateTime<Utc>,
+    signature: Option<&str>,
+) -> Result<Vec<u8>, RequestError> {
+    let url = request.url();
+    let mut rendered = Vec::new();
+    let mut last = 0;
+    for captures in template_regex().captures_iter(template.as_bytes()) {
+        let whole = captures.get(0).expect("Group 0 always matches.");
+        rendered.extend_from_slice(&template.as_bytes()[last..whole.start()]);
+        last = whole.end();
+
+        let Some(name) = captures.get(1) else {
+            rendered.push(b'\n');
+            continue;
+        };
+        let value: Option<Vec<u8>> = match name.as_bytes() {
+            b"method" => Some(request.method().as_str().into()),
+            b"url" => Some(url.as_str().into()),
+            b"host" => Some(url.host_str().unwrap_or_default().into()),
+            b"path" => Some(url.path().into()),
+            b"query" => Some(url.query().unwrap_or_default().into()),
+            b"timestamp" => Some(now.timestamp().to_string().into()),
+            b"timestamp_ms" => Some(now.timestamp_millis().to_string().into()),
+            b"body" => Some(match request.body() {
+                None => Vec::new(),
+                Some(body) => body
+                    .as_bytes()
+                    .ok_or_else(|| {
+                        RequestError::Other(
+                            "A streamed body cannot be part of the signature".to_owned(),
+                        )
+                    })?
+                    .to_vec(),
+            }),
+            b"signature" => signature.map(Into::into),
+            _ => None,
+        };
+        rendered.extend(value.unwrap_or_else(|| whole.as_bytes().to_vec()));
+    }
+    rendered.extend_from_slice(&template.as_bytes()[last..]);
+    Ok(rendered)
+}
@@ -0,0 +1,112 @@
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+
+use crate::request_body;
+
+/// Re-indents a JSON document without parsing it into a `Value` first, so
+/// numbers like `1.10` or `12345678901234567890` and string escapes show
+/// exactly as the server sent them. Text that is not JSON is left alone.
+pub fn pretty_print(text: &str) -> String {
+    request_body::format_json(text).unwrap_or_else(|_| text.to_owned())
+}
+
+/// Colors keys, strings, numbers and literals of already pretty-printed JSON.
+/// Pretty-printed strings never contain raw newlines, so every line can be
+/// tokenized on its own.
+pub fn highlight(text: &str) -> Vec<Line<'static>> {
+    text.lines().map(highlight_line).collect()
+}
+
+fn highlight_line(line: &str) -> Line<'static> {
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut chars = line.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let end = match c {
+            '"' => {
+                let mut escaped = false;
+                let mut end = line.len();
+                for (index, c) in chars.by_ref() {
+                    match c {
+                        '\\' if !escaped => escaped = true,
+                        '"' if !escaped => {
+                            end = index + 1;
+                            break;
+                        }
+                        _ => escaped = false,
+                    }
+                }
+                end
+            }
+            '-' | '0'..='9' | 't' | 'f' | 'n' => {
+                let mut end = line.len();
+                while let Some(&(index, c)) = chars.peek() {
+                    if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+') {
+                        chars.next();
+                    } else {
+                        end = index;
+                        break;
+                    }
+                }
+                end
+            }
+            _ => {
+                plain.push(c);
+                continue;
+            }
+        };
+
+        if !plain.is_empty() {
+            spans.push(Span::raw(std::mem::take(&mut plain)));
+        }
+
+        let token = &line[start..end];
+        let is_key = token.starts_with('"') && line[end..].trim_start().starts_with(':');
+        let color = match token {
+            _ if is_key => Color::Cyan,
+            "true" | "false" => Color::Magenta,
+            "null" => Color::DarkGray,
+            _ if token.starts_with('"') => Color::Green,
+            _ => Color::Yellow,
+        };
+        spans.push(Span::styled(token.to_owned(), Style::default().fg(color)));
+    }
+
+    if !plain.is_empty() {
+        spans.push(Span::raw(plain));
+    }
+
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    #[test]
+    fn keeps_numbers_as_sent() {
+        assert_eq!(
+            pretty_print(r#"{"big":123456789012345678901234567890,"price":1.10,"n":1e3}"#),
+            "{\n  \"big\": 123456789012345678901234567890,\n  \"price\": 1.10,\n  \"n\": 1e3\n}"
+        );
+    }
+
+    #[test]
+    fn keeps_numbers_of_filtered_values() {
+        let value: Value = serde_json::from_str(r#"[-98765432109876543210, 1.10]"#).unwrap();
+        assert_eq!(
+            pretty_print(&value.to_string()),
+            "[\n  -98765432109876543210,\n  1.10\n]"
+        );
+    }
+
+    #[test]
+    fn leaves_other_text_alone() {
+        assert_eq!(pretty_print("not json"), "not json");
+    }
+}
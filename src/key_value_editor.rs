@@ -0,0 +1,384 @@
+use std::path::Path;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Paragraph},
+    Frame,
+};
+use regex::Regex;
+use reqwest::header::HeaderName;
+
+use crate::{collection::KeyValue, text_input::TextInput};
+
+const COLUMNS: [&str; 4] = ["", "Key", "Value", "Description"];
+const CHECKBOX_WIDTH: u16 = 4;
+/// Shown in front of the value of file rows.
+const FILE_LABEL: &str = "file: ";
+
+/// What the rows of a table are, which decides how they are checked.
+#[derive(Clone, Copy, PartialEq)]
+pub enum TableKind {
+    Query,
+    /// Keys are header names.
+    Headers,
+    Form,
+    /// Values can also be paths of files to upload.
+    Multipart,
+}
+
+struct Row<'a> {
+    enabled: bool,
+    file: bool,
+    /// Key, value and description.
+    inputs: [TextInput<'a>; 3],
+    /// Whether the row uploads a file that is not there, looked up when the
+    /// row changes rather than on every frame.
+    missing_file: bool,
+}
+
+impl<'a> Row<'a> {
+    fn new() -> Self {
+        Self {
+            enabled: true,
+            file: false,
+            inputs: [TextInput::new(""), TextInput::new(""), TextInput::new("")],
+            missing_file: false,
+        }
+    }
+
+    fn key(&self) -> &str {
+        self.inputs[0].text()
+    }
+
+    fn value(&self) -> &str {
+        self.inputs[1].text()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.inputs.iter().all(|input| input.text().is_empty())
+    }
+
+    /// Paths with placeholders are only checked once they are resolved.
+    fn check_file(&mut self) {
+        let path = self.value();
+        self.missing_file = self.file && !path.contains("{{") && !Path::new(path).is_file();
+    }
+}
+
+/// The Query and Headers tabs and form bodies: a table of rows that can each
+/// be switched off without deleting them. There is always at least one row to
+/// type into.
+pub struct KeyValueEditor<'a> {
+    rows: Vec<Row<'a>>,
+    pub selected_row: usize,
+    /// 0 is the checkbox, then key, value and description.
+    pub selected_column: usize,
+    kind: TableKind,
+}
+
+impl<'a> KeyValueEditor<'a> {
+    pub fn new(kind: TableKind) -> Self {
+        Self {
+            rows: vec![Row::new()],
+            selected_row: 0,
+            selected_column: 1,
+            kind,
+        }
+    }
+
+    pub fn from_rows(rows: &[KeyValue], kind: TableKind) -> Self {
+        let mut editor = Self::new(kind);
+        editor.set_rows(rows);
+        editor
+    }
+
+    pub fn set_rows(&mut self, rows: &[KeyValue]) {
+        self.rows = rows
+            .iter()
+            .map(|key_value| {
+                let mut row = Row::new();
+                row.enabled = key_value.enabled;
+                row.file = key_value.file && self.kind == TableKind::Multipart;
+                for (input, text) in row.inputs.iter_mut().zip([
+                    &key_value.key,
+                    &key_value.value,
+                    &key_value.description,
+                ]) {
+                    input.set_text(text);
+                }
+                row.check_file();
+                row
+            })
+            .collect();
+        if self.rows.is_empty() {
+            self.rows.push(Row::new());
+        }
+        self.selected_row = self.selected_row.min(self.rows.len() - 1);
+    }
+
+    /// Replaces the rows that are switched on with `pairs`. Descriptions stay
+    /// with rows that keep their key, and rows that are off move to the end.
+    pub fn set_enabled_pairs(&mut self, pairs: Vec<(String, String)>) {
+        let old_rows = self.to_rows();
+        let mut old_enabled = old_rows.iter().filter(|row| row.enabled);
+        let rows: Vec<KeyValue> = pairs
+            .into_iter()
+            .map(|(key, value)| {
+                let description = old_enabled
+                    .next()
+                    .filter(|row| row.key == key)
+                    .map(|row| row.description.clone())
+                    .unwrap_or_default();
+                KeyValue {
+                    key,
+                    value,
+                    description,
+                    ..KeyValue::default()
+                }
+            })
+            .chain(old_rows.iter().filter(|row| !row.enabled).cloned())
+            .collect();
+        self.set_rows(&rows);
+    }
+
+    /// The rows worth keeping, empty ones are left out.
+    pub fn to_rows(&self) -> Vec<KeyValue> {
+        self.rows
+            .iter()
+            .filter(|row| !row.is_empty())
+            .map(|row| KeyValue {
+                key: row.key().to_owned(),
+                value: row.value().to_owned(),
+                description: row.inputs[2].text().to_owned(),
+                enabled: row.enabled,
+                file: row.file,
+            })
+            .collect()
+    }
+
+    /// Keys and values of the rows that are sent.
+    pub fn enabled_pairs(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.rows
+            .iter()
+            .filter(|row| row.enabled && !row.key().trim().is_empty())
+            .map(|row| (row.key(), row.value()))
+    }
+
+    /// Keys, values and whether the value is a file path, of the rows that
+    /// are sent.
+    pub fn enabled_parts(&self) -> impl Iterator<Item = (&str, &str, bool)> {
+        self.rows
+            .iter()
+            .filter(|row| row.enabled && !row.key().trim().is_empty())
+            .map(|row| (row.key(), row.value(), row.file))
+    }
+
+    /// Keys and values, for placeholder highlighting.
+    pub fn texts(&self) -> impl Iterator<Item = &str> {
+        self.rows.iter().flat_map(|row| [row.key(), row.value()])
+    }
+
+    /// Why the key of `row` cannot be sent. Keys with placeholders are only
+    /// checked once they are resolved.
+    fn key_error(&self, row: &Row) -> Option<String> {
+        let key = row.key().trim();
+        let is_invalid = self.kind == TableKind::Headers
+            && !key.is_empty()
+            && !key.contains("{{")
+            && HeaderName::from_bytes(key.as_bytes()).is_err();
+        is_invalid.then(|| format!("'{key}' is not a valid header name"))
+    }
+
+    /// Why the file of `row` cannot be uploaded.
+    fn value_error(&self, row: &Row) -> Option<String> {
+        row.missing_file
+            .then(|| format!("'{}' is not a file", row.value()))
+    }
+
+    pub fn on_key(&mut self, key: &KeyEvent) {
+        let last_row = self.rows.len() - 1;
+        match key.code {
+            KeyCode::Up if key.modifiers == KeyModifiers::ALT => {
+                if self.selected_row > 0 {
+                    self.rows.swap(self.selected_row, self.selected_row - 1);
+                    self.selected_row -= 1;
+                }
+            }
+            KeyCode::Down if key.modifiers == KeyModifiers::ALT => {
+                if self.selected_row < last_row {
+                    self.rows.swap(self.selected_row, self.selected_row + 1);
+                    self.selected_row += 1;
+                }
+            }
+            KeyCode::Char('t')
+                if key.modifiers == KeyModifiers::ALT && self.kind == TableKind::Multipart =>
+            {
+                let row = &mut self.rows[self.selected_row];
+                row.file = !row.file;
+                row.check_file();
+            }
+            KeyCode::Char('d') if key.modifiers == KeyModifiers::ALT => {
+                self.rows.remove(self.selected_row);
+                if self.rows.is_empty() {
+                    self.rows.push(Row::new());
+                }
+                self.selected_row = self.selected_row.min(self.rows.len() - 1);
+            }
+            KeyCode::Up => self.selected_row = self.selected_row.saturating_sub(1),
+            KeyCode::Down => self.selected_row = (self.selected_row + 1).min(last_row),
+            KeyCode::Tab => self.selected_column = (self.selected_column + 1) % COLUMNS.len(),
+            KeyCode::BackTab => {
+                self.selected_column = (self.selected_column + COLUMNS.len() - 1) % COLUMNS.len()
+            }
+            KeyCode::Enter => {
+                self.rows.insert(self.selected_row + 1, Row::new());
+                self.selected_row += 1;
+                self.selected_column = 1;
+            }
+            KeyCode::Char(' ') if self.selected_column == 0 => {
+                let row = &mut self.rows[self.selected_row];
+                row.enabled = !row.enabled;
+            }
+            _ if self.selected_column == 0 => {}
+            code => {
+                let row = &mut self.rows[self.selected_row];
+                row.inputs[self.selected_column - 1].on_input(code);
+                if self.selected_column == 2 {
+                    row.check_file();
+                }
+            }
+        }
+    }
+}
+
+/// Draws the column titles, one line per row and a footer with the keys.
+/// `focused` is set when the tab is the selected input, which highlights the
+/// selected cell and shows the cursor in it.
+pub fn render_key_value_editor(
+    f: &mut Frame,
+    editor: &KeyValueEditor,
+    block: Block,
+    area: Rect,
+    focused: bool,
+    unresolved: Option<Regex>,
+) {
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let [title_area, rows_area, footer_area] = {
+        let areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Min(0),
+                Constraint::Length(1),
+            ])
+            .split(inner);
+        [areas[0], areas[1], areas[2]]
+    };
+    let columns = |area: Rect| {
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(CHECKBOX_WIDTH),
+                Constraint::Percentage(30),
+                Constraint::Percentage(40),
+                Constraint::Min(0),
+            ])
+            .split(area)
+    };
+
+    for (title, cell) in COLUMNS.iter().zip(columns(title_area).iter()) {
+        f.render_widget(Paragraph::new(*title).bold(), *cell);
+    }
+
+    // Keep the selected row on screen.
+    let height = rows_area.height as usize;
+    let first_row = (editor.selected_row + 1).saturating_sub(height);
+    for (index, row) in editor.rows.iter().enumerate().skip(first_row).take(height) {
+        let row_area = Rect {
+            y: rows_area.y + (index - first_row) as u16,
+            height: 1,
+            ..rows_area
+        };
+        let cells = columns(row_area);
+        let selected_row = focused && index == editor.selected_row;
+        let text_style = if row.enabled {
+            Style::default()
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+
+        let checkbox = Paragraph::new(if row.enabled { "[x]" } else { "[ ]" });
+        let checkbox = if selected_row && editor.selected_column == 0 {
+            checkbox.fg(Color::Red).bold()
+        } else {
+            checkbox.style(text_style)
+        };
+        f.render_widget(checkbox, cells[0]);
+
+        for (column, input) in row.inputs.iter().enumerate() {
+            let mut cell = Rect {
+                width: cells[column + 1].width.saturating_sub(1),
+                ..cells[column + 1]
+            };
+            if column == 1 && row.file {
+                let label = Span::raw(FILE_LABEL).fg(Color::DarkGray);
+                f.render_widget(Paragraph::new(Line::from(label)), cell);
+                let label_width = (FILE_LABEL.len() as u16).min(cell.width);
+                cell.x += label_width;
+                cell.width -= label_width;
+            }
+            let is_invalid = match column {
+                0 => editor.key_error(row).is_some(),
+                1 => editor.value_error(row).is_some(),
+                _ => false,
+            };
+            let style = if is_invalid {
+                Style::default().fg(Color::Red)
+            } else {
+                text_style
+            };
+            // Other cells show the start of their text, the one being edited
+            // scrolls to keep the cursor in view.
+            let mut scroll = 0;
+            if selected_row && editor.selected_column == column + 1 {
+                scroll = input.scroll_offset(cell.width);
+                let cursor = (input.cursor_position() - scroll) as u16;
+                f.set_cursor(cell.x + cursor, cell.y);
+                f.render_widget(Paragraph::new("").style(style.underlined()), cell);
+            } else {
+                f.render_widget(Paragraph::new("").style(style), cell);
+            }
+            let input = input
+                .clone()
+                .highlight(
+                    unresolved.clone(),
+                    Style::default().fg(Color::White).bg(Color::Red),
+                )
+                .scroll(scroll);
+            f.render_widget(input, cell);
+        }
+    }
+
+    let error = editor
+        .rows
+        .iter()
+        .find_map(|row| editor.key_error(row).or_else(|| editor.value_error(row)));
+    let footer = match error {
+        Some(err) => Line::styled(err, Style::default().fg(Color::Red)),
+        None if editor.kind == TableKind::Multipart => Line::styled(
+            "Tab next column, Enter add row, Space toggle, Alt + t text/file, Alt + ↑/↓ move, \
+             Alt + d delete",
+            Style::default().fg(Color::DarkGray),
+        ),
+        None => Line::styled(
+            "Tab next column, Enter add row, Space toggle, Alt + ↑/↓ move, Alt + d delete",
+            Style::default().fg(Color::DarkGray),
+        ),
+    };
+    f.render_widget(Paragraph::new(footer), footer_area);
+}
@@ -1,6 +1,31 @@
 mod app;
+mod auth;
+mod auth_editor;
 mod body;
+mod collection;
+mod crypto;
+mod curl;
+mod digest;
+mod environment;
+mod export;
+mod export_popup;
+mod file_picker;
+mod filter;
+mod graphql;
 mod header;
+mod history;
+mod history_popup;
+mod hmac_auth;
+mod json;
+mod key_value_editor;
+mod oauth;
+mod query;
+mod request;
+mod request_body;
+mod response;
+mod search;
+mod sidebar;
+mod sigv4;
 mod text_input;
 
 use std::{
@@ -9,7 +34,10 @@ use std::{
     time::{Duration, Instant},
 };
 
-use app::{App, RequestType, SelectionState};
+use app::{
+    is_within_block, App, PromptAction, RequestType, ResponseState, SelectionState,
+    CUSTOM_REQUEST_TYPE,
+};
 use crossterm::{
     event::{
         self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
@@ -22,10 +50,13 @@ use ratatui::{
     backend::{Backend, CrosstermBackend},
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style, Stylize},
-    widgets::{Block, Borders, Clear, List, ListItem},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState},
     Frame, Terminal,
 };
-use reqwest::header::HeaderMap;
+use request::{PendingRequest, PreparedRequest};
+use request_body::BodyType;
+
+const MOUSE_SCROLL_LINES: usize = 3;
 
 fn main() -> Result<(), Box<dyn Error>> {
     // setup terminal
@@ -36,8 +67,9 @@ fn main() -> Result<(), Box<dyn Error>> {
     let mut terminal = Terminal::new(backend)?;
 
     // create app and run it
-    let tick_rate = Duration::from_millis(250);
-    let app = App::new();
+    let tick_rate = Duration::from_millis(100);
+    let mut app = App::new();
+    app.refresh_collection();
     let res = run_app(&mut terminal, app, tick_rate);
 
     // restore terminal
@@ -61,54 +93,53 @@ fn close_popup(app: &mut App) {
     app.selected_input = SelectionState::RequestType;
 }
 
-fn send_request<'a>(app: &App<'a>) -> reqwest::Result<String> {
-    let method = match app.selected_request_type {
-        RequestType::Get => reqwest::Method::GET,
-        RequestType::Post => reqwest::Method::POST,
-        RequestType::Put => reqwest::Method::PUT,
-        RequestType::Delete => reqwest::Method::DELETE,
-        RequestType::Head => reqwest::Method::HEAD,
-        RequestType::Options => reqwest::Method::OPTIONS,
-    };
-    let client = reqwest::blocking::Client::new();
-
-    let query_params: Vec<(&str, &str)> = app
-        .widgets
-        .query_params_text_area
-        .lines()
-        .iter()
-        .filter_map(|query_param_line| query_param_line.split_once(":"))
-        .collect();
-
-    let headers: HeaderMap =
-        HeaderMap::from_iter(app.widgets.headers_text_area.lines().iter().filter_map(
-            |header_line| {
-                let (header_key, header_value) = header_line.split_once(":")?;
-                Some((header_key.parse().ok()?, header_value.parse().ok()?))
-            },
-        ));
-
-    let body = app.widgets.body_text_area.lines().join("");
-
-    let response = client
-        .request(method, app.widgets.url_text_input.text())
-        .query(&query_params)
-        .headers(headers)
-        .body(body)
-        .send()?;
-
-    let response_body = response.text()?;
-
-    Ok(response_body)
-}
-
 fn dispatch_events_pre(key: &KeyEvent, app: &mut App) {
     if let KeyCode::Char(c) = key.code {
         if key.modifiers == KeyModifiers::CONTROL {
             match c {
                 'r' => {
-                    let response = send_request(&app).expect("Could not send request");
-                    app.response_text = response;
+                    if app.pending_request.is_none() {
+                        match PreparedRequest::from_app(app) {
+                            Ok(request) => {
+                                let saved_request = app.saved_request();
+                                app.pending_request =
+                                    Some(PendingRequest::spawn(request, saved_request))
+                            }
+                            Err(err) => {
+                                app.response = ResponseState::Failed(err);
+                                app.refresh_response_view();
+                            }
+                        }
+                    }
+                    return;
+                }
+                'e' => {
+                    app.open_environment_popup();
+                    return;
+                }
+                's' => {
+                    app.save_request();
+                    return;
+                }
+                'l' => {
+                    app.open_history_popup();
+                    return;
+                }
+                'o' => {
+                    app.open_import_popup();
+                    return;
+                }
+                'y' => {
+                    app.open_export_popup();
+                    return;
+                }
+                'x' => {
+                    app.cancel_pending_request();
+                    return;
+                }
+                't' => {
+                    app.pretty_response = !app.pretty_response;
+                    app.refresh_response_view();
                     return;
                 }
                 'n' => {
@@ -118,28 +149,196 @@ fn dispatch_events_pre(key: &KeyEvent, app: &mut App) {
                 'p' => {
                     app.selected_input = app.selected_input.previous();
                     return;
-                },
+                }
                 _ => {}
             }
         }
     }
 
     match app.selected_input {
-        SelectionState::Url => app.widgets.url_text_input.on_input(key.code),
+        SelectionState::Url => {
+            app.widgets.url_text_input.on_input(key.code);
+            app.sync_query_from_url();
+            app.forget_stale_graphql_schema();
+        }
+        SelectionState::ResponseFilter => {
+            if key.code == KeyCode::Enter {
+                app.selected_input = SelectionState::ResponseBody;
+            } else {
+                app.response_filter.on_input(key.code);
+                app.response_scroll = 0;
+                app.refresh_response_view();
+            }
+        }
+        SelectionState::ResponseBody => dispatch_search_events(key, app),
+        SelectionState::Sidebar => dispatch_sidebar_events(key, app),
+        SelectionState::Prompt => dispatch_prompt_events(key, app),
+        SelectionState::HistoryPopup => {
+            app.history_filter.on_input(key.code);
+            if let KeyCode::Char(_) | KeyCode::Backspace | KeyCode::Delete = key.code {
+                app.history_state.select(Some(0));
+            }
+        }
+        SelectionState::ExportPopup => dispatch_export_events(key, app),
+        SelectionState::ImportPopup if key.code != KeyCode::Enter => {
+            let _ = app.import_text_area.input(*key);
+        }
         SelectionState::RequestBody => {
-            let text_area = match app.tabs.index {
-                0 => &mut app.widgets.query_params_text_area,
-                1 => &mut app.widgets.headers_text_area,
-                2 => &mut app.widgets.body_text_area,
-                3 => &mut app.widgets.auth_text_area,
+            match app.tabs.index {
+                0 => {
+                    app.widgets.query_editor.on_key(key);
+                    app.sync_url_from_query();
+                }
+                1 => app.widgets.headers_editor.on_key(key),
+                2 => dispatch_body_events(key, app),
+                3 => app.widgets.auth_editor.on_key(key),
                 _ => unreachable!("Index out of bounds for tabs."),
-            };
-            let _ = text_area.input(*key);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn dispatch_body_events(key: &KeyEvent, app: &mut App) {
+    match key.code {
+        KeyCode::Left | KeyCode::Right if key.modifiers == KeyModifiers::ALT => {
+            app.body_type = app.body_type.cycle(key.code == KeyCode::Right);
+            // GraphQL goes over POST, a GET would leave the body out.
+            if app.body_type == BodyType::GraphQl
+                && matches!(app.selected_request_type, RequestType::Get)
+            {
+                app.selected_request_type = RequestType::Post;
+            }
+        }
+        KeyCode::Char('f')
+            if key.modifiers == KeyModifiers::ALT && app.body_type == BodyType::Json =>
+        {
+            app.format_json_body();
+        }
+        _ => match app.body_type {
+            // There is no text to edit.
+            BodyType::None => {}
+            BodyType::Form => app.widgets.form_editor.on_key(key),
+            BodyType::Multipart => app.widgets.multipart_editor.on_key(key),
+            BodyType::Binary => {
+                app.widgets.body_file_input.on_input(key.code);
+                app.refresh_body_file();
+            }
+            BodyType::Raw | BodyType::Json | BodyType::Xml => {
+                let _ = app.widgets.body_text_area.input(*key);
+            }
+            BodyType::GraphQl => dispatch_graphql_events(key, app),
+        },
+    }
+    app.refresh_body_error();
+}
+
+fn dispatch_graphql_events(key: &KeyEvent, app: &mut App) {
+    match key.code {
+        KeyCode::Up | KeyCode::Down if key.modifiers == KeyModifiers::ALT => {
+            app.graphql_variables_focused = key.code == KeyCode::Down;
+        }
+        KeyCode::Char('r') if key.modifiers == KeyModifiers::ALT => app.fetch_graphql_schema(),
+        KeyCode::Tab if !app.graphql_variables_focused && app.complete_graphql_field() => {}
+        _ if app.graphql_variables_focused => {
+            let _ = app.widgets.graphql_variables_area.input(*key);
+        }
+        _ => {
+            let _ = app.widgets.graphql_query_area.input(*key);
+        }
+    }
+}
+
+fn dispatch_sidebar_events(key: &KeyEvent, app: &mut App) {
+    let Some(entry) = app.selected_collection_entry() else {
+        return;
+    };
+    let path = entry.path.clone();
+    let is_folder = entry.is_folder;
+
+    match key.code {
+        KeyCode::Char('r') => app.open_prompt(PromptAction::Rename { path, is_folder }),
+        KeyCode::Char('d') if !is_folder => {
+            match collection::duplicate_request(&path) {
+                Ok(copy_path) => {
+                    app.collection_message = Some(format!("Duplicated to {}", copy_path.display()));
+                    app.refresh_collection();
+                    app.select_collection_entry(&copy_path);
+                }
+                Err(err) => app.collection_message = Some(err),
+            }
+        }
+        KeyCode::Delete | KeyCode::Char('x') => {
+            app.open_prompt(PromptAction::Delete { path, is_folder })
         }
         _ => {}
     }
 }
 
+fn dispatch_prompt_events(key: &KeyEvent, app: &mut App) {
+    let Some(prompt) = &mut app.prompt else {
+        return;
+    };
+
+    if let PromptAction::Delete { .. } = prompt.action {
+        match key.code {
+            KeyCode::Char('y') => app.confirm_prompt(),
+            KeyCode::Char('n') => app.close_prompt(),
+            _ => {}
+        }
+        return;
+    }
+
+    prompt.input.on_input(key.code);
+}
+
+fn dispatch_export_events(key: &KeyEvent, app: &mut App) {
+    if let Some(input) = &mut app.export_file {
+        input.on_input(key.code);
+        return;
+    }
+
+    match key.code {
+        KeyCode::Char('c') => app.copy_export(),
+        KeyCode::Char('w') => app.start_export_file(),
+        _ => {}
+    }
+}
+
+fn dispatch_search_events(key: &KeyEvent, app: &mut App) {
+    if !app.search.editing {
+        match key.code {
+            KeyCode::Char('/') => app.search.open(),
+            KeyCode::Char('n') => {
+                app.search.next();
+                app.reveal_current_match();
+            }
+            KeyCode::Char('N') => {
+                app.search.previous();
+                app.reveal_current_match();
+            }
+            _ => {}
+        }
+        return;
+    }
+
+    match key.code {
+        KeyCode::Enter => app.search.editing = false,
+        KeyCode::Char('c') if key.modifiers == KeyModifiers::ALT => {
+            app.search.case_sensitive = !app.search.case_sensitive;
+            app.update_search();
+        }
+        KeyCode::Char('r') if key.modifiers == KeyModifiers::ALT => {
+            app.search.regex = !app.search.regex;
+            app.update_search();
+        }
+        _ => {
+            app.search.input.on_input(key.code);
+            app.update_search();
+        }
+    }
+}
+
 fn run_app<B: Backend>(
     terminal: &mut Terminal<B>,
     mut app: App,
@@ -154,10 +353,20 @@ fn run_app<B: Backend>(
             let event = event::read()?;
 
             if let Event::Mouse(mouse) = event {
-                if mouse.kind == MouseEventKind::Down(MouseButton::Left) {
-                    let x = mouse.column;
-                    let y = mouse.row;
-                    app.last_mouse_down_event = Some((x, y));
+                let position = (mouse.column, mouse.row);
+                match mouse.kind {
+                    MouseEventKind::Down(MouseButton::Left) => {
+                        app.last_mouse_down_event = Some(position);
+                    }
+                    MouseEventKind::ScrollUp if is_within_block(&app.response_area, position) => {
+                        app.scroll_response_up(MOUSE_SCROLL_LINES);
+                    }
+                    MouseEventKind::ScrollDown
+                        if is_within_block(&app.response_area, position) =>
+                    {
+                        app.scroll_response_down(MOUSE_SCROLL_LINES);
+                    }
+                    _ => {}
                 }
             }
 
@@ -166,6 +375,22 @@ fn run_app<B: Backend>(
                     if key.code == KeyCode::Esc {
                         if app.show_request_type_popup {
                             close_popup(&mut app);
+                        } else if app.show_environment_popup {
+                            app.close_environment_popup();
+                        } else if app.prompt.is_some() {
+                            app.close_prompt();
+                        } else if app.show_history_popup {
+                            app.close_history_popup();
+                        } else if app.show_import_popup {
+                            app.close_import_popup();
+                        } else if app.export_file.is_some() {
+                            app.export_file = None;
+                        } else if app.show_export_popup {
+                            app.close_export_popup();
+                        } else if app.file_picker.is_some() {
+                            app.close_file_picker();
+                        } else if app.search.visible {
+                            app.search.close();
                         } else {
                             return Ok(());
                         }
@@ -180,11 +405,34 @@ fn run_app<B: Backend>(
                                 app.selected_input = SelectionState::Popup;
                             }
                             SelectionState::Popup => {
-                                if let Some(request_type) = app.request_types.get() {
-                                    app.selected_request_type = RequestType::from(*request_type);
+                                if let Some(&request_type) = app.request_types.get() {
                                     close_popup(&mut app);
+                                    if request_type == CUSTOM_REQUEST_TYPE {
+                                        app.open_prompt(PromptAction::CustomMethod);
+                                    } else {
+                                        app.selected_request_type = RequestType::from(request_type);
+                                    }
                                 }
                             }
+                            SelectionState::EnvironmentPopup => app.select_environment(),
+                            SelectionState::Sidebar => app.open_selected_entry(),
+                            // Deleting takes an explicit 'y', a stray Enter must not do it.
+                            SelectionState::Prompt
+                                if !app.prompt.as_ref().is_some_and(|prompt| {
+                                    matches!(prompt.action, PromptAction::Delete { .. })
+                                }) =>
+                            {
+                                app.confirm_prompt()
+                            }
+                            SelectionState::HistoryPopup => app.restore_history_entry(),
+                            SelectionState::ImportPopup => app.confirm_import(),
+                            SelectionState::ExportPopup => app.write_export_file(),
+                            SelectionState::FilePicker => app.choose_file(),
+                            SelectionState::RequestBody
+                                if app.tabs.index == 2 && app.body_type == BodyType::Binary =>
+                            {
+                                app.open_file_picker()
+                            }
                             SelectionState::RequestTabs => {
                                 app.selected_input = app.selected_input.next();
                             }
@@ -192,20 +440,75 @@ fn run_app<B: Backend>(
                         },
                         KeyCode::Left => match app.selected_input {
                             SelectionState::RequestTabs => app.tabs.move_left(),
+                            SelectionState::ResponseTabs => app.response_tabs.move_left(),
+                            SelectionState::ExportPopup if app.export_file.is_none() => {
+                                app.export_formats.move_left()
+                            }
                             _ => {}
                         },
                         KeyCode::Right => match app.selected_input {
                             SelectionState::RequestTabs => app.tabs.move_right(),
+                            SelectionState::ResponseTabs => app.response_tabs.move_right(),
+                            SelectionState::ExportPopup if app.export_file.is_none() => {
+                                app.export_formats.move_right()
+                            }
+                            _ => {}
+                        },
+                        KeyCode::Down => match app.selected_input {
+                            SelectionState::Popup => app.request_types.next(),
+                            SelectionState::EnvironmentPopup => app.environment_list.next(),
+                            SelectionState::Sidebar => app.select_next_collection_entry(),
+                            SelectionState::HistoryPopup => app.select_next_history_entry(),
+                            SelectionState::FilePicker => {
+                                if let Some(picker) = &mut app.file_picker {
+                                    picker.select_next();
+                                }
+                            }
+                            SelectionState::ResponseBody => app.scroll_response_down(1),
+                            _ => {}
+                        },
+                        KeyCode::Up => match app.selected_input {
+                            SelectionState::Popup => app.request_types.previous(),
+                            SelectionState::EnvironmentPopup => app.environment_list.previous(),
+                            SelectionState::Sidebar => app.select_previous_collection_entry(),
+                            SelectionState::HistoryPopup => app.select_previous_history_entry(),
+                            SelectionState::FilePicker => {
+                                if let Some(picker) = &mut app.file_picker {
+                                    picker.select_previous();
+                                }
+                            }
+                            SelectionState::ResponseBody => app.scroll_response_up(1),
                             _ => {}
                         },
-                        KeyCode::Down => app.request_types.next(),
-                        KeyCode::Up => app.request_types.previous(),
+                        KeyCode::PageDown => {
+                            if let SelectionState::ResponseBody = app.selected_input {
+                                app.scroll_response_down(app.response_page_height())
+                            }
+                        }
+                        KeyCode::PageUp => {
+                            if let SelectionState::ResponseBody = app.selected_input {
+                                app.scroll_response_up(app.response_page_height())
+                            }
+                        }
+                        KeyCode::Home => {
+                            if let SelectionState::ResponseBody = app.selected_input {
+                                app.scroll_response_to(0)
+                            }
+                        }
+                        KeyCode::End => {
+                            if let SelectionState::ResponseBody = app.selected_input {
+                                app.scroll_response_to(usize::MAX)
+                            }
+                        }
                         _ => {}
                     }
                 }
             }
         }
         if last_tick.elapsed() >= tick_rate {
+            app.poll_pending_request();
+            app.poll_graphql_schema();
+            app.tick_count = app.tick_count.wrapping_add(1);
             last_tick = Instant::now();
         }
     }
@@ -231,40 +534,140 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 }
 
 fn ui(f: &mut Frame, app: &mut App) {
+    app.refresh_variable_highlights();
+
+    let sidebar_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(30), Constraint::Min(0)])
+        .split(f.size());
+
+    sidebar::render_sidebar(f, app, &sidebar_chunks[0]);
+
     let header_chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([Constraint::Length(3), Constraint::Min(0)])
-        .split(f.size());
+        .split(sidebar_chunks[1]);
 
     header::render_header(f, app, &header_chunks[0]);
     body::render_body(f, app, &header_chunks[1]);
 
     if app.show_request_type_popup {
-        let items: Vec<ListItem> = app
-            .request_types
-            .items
-            .iter()
-            .map(|item| {
-                ListItem::new(*item).style(Style::default().fg(Color::Black).bg(Color::White))
-            })
-            .collect();
-
-        let list = List::new(items)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .border_style(Style::new().red())
-                    .title("Select Request Type"),
-            )
-            .highlight_style(
-                Style::default()
-                    .bg(Color::LightGreen)
-                    .add_modifier(Modifier::BOLD),
-            )
-            .highlight_symbol(">> ");
-
-        let area = centered_rect(50, 50, f.size());
-        f.render_widget(Clear, area);
-        f.render_stateful_widget(list, area, &mut app.request_types.state);
+        render_list_popup(
+            f,
+            "Select Request Type",
+            &app.request_types.items,
+            &mut app.request_types.state,
+        );
+    }
+
+    render_prompt(f, app);
+
+    if app.show_history_popup {
+        history_popup::render_history_popup(f, app);
+    }
+
+    render_import_popup(f, app);
+
+    if app.show_export_popup {
+        export_popup::render_export_popup(f, app);
+    }
+
+    if let Some(picker) = &mut app.file_picker {
+        render_list_popup(
+            f,
+            &picker.title(),
+            &picker.entries.items,
+            &mut picker.entries.state,
+        );
+    }
+
+    if app.show_environment_popup {
+        let title = match &app.environment_error {
+            Some(err) => format!("Select Environment ({err})"),
+            None => "Select Environment".to_owned(),
+        };
+        render_list_popup(
+            f,
+            &title,
+            &app.environment_list.items,
+            &mut app.environment_list.state,
+        );
     }
 }
+
+fn render_prompt(f: &mut Frame, app: &App) {
+    let Some(prompt) = &app.prompt else {
+        return;
+    };
+
+    let popup_area = centered_rect(50, 100, f.size());
+    let area = Rect {
+        y: popup_area.y + popup_area.height.saturating_sub(3) / 2,
+        height: 3,
+        ..popup_area
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::new().red())
+        .title(prompt.title());
+    f.render_widget(Clear, area);
+    if let PromptAction::Delete { .. } = prompt.action {
+        f.render_widget(block, area);
+    } else {
+        f.set_cursor(area.x + prompt.input.cursor_position() as u16 + 1, area.y + 1);
+        f.render_widget(prompt.input.clone().block(block), area);
+    }
+}
+
+fn render_import_popup(f: &mut Frame, app: &mut App) {
+    if !app.show_import_popup {
+        return;
+    }
+
+    let area = centered_rect(70, 50, f.size());
+    let title = match &app.import_error {
+        Some(err) => format!("Import curl: {err}"),
+        None => "Paste a curl command, Enter to import".to_owned(),
+    };
+    app.import_text_area.set_block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::new().red())
+            .title(title),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(app.import_text_area.widget(), area);
+}
+
+fn render_list_popup<T: AsRef<str>>(
+    f: &mut Frame,
+    title: &str,
+    items: &[T],
+    state: &mut ListState,
+) {
+    let items: Vec<ListItem> = items
+        .iter()
+        .map(|item| {
+            ListItem::new(item.as_ref()).style(Style::default().fg(Color::Black).bg(Color::White))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::new().red())
+                .title(title),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::LightGreen)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+
+    let area = centered_rect(50, 50, f.size());
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, state);
+}
@@ -0,0 +1,416 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard, OnceLock},
+    time::{Duration, Instant},
+};
+
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{crypto::sha256_hex, request::RequestError};
+
+/// Tokens are renewed this long before they expire, so they do not run out
+/// while a request is on its way.
+const REFRESH_MARGIN: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OAuthGrant {
+    #[default]
+    ClientCredentials,
+    Password,
+    RefreshToken,
+}
+
+/// How the client id and secret are sent to the token endpoint.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientAuthentication {
+    #[default]
+    BasicHeader,
+    Body,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct OAuthConfig {
+    pub grant: OAuthGrant,
+    pub token_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scope: String,
+    pub username: String,
+    pub password: String,
+    pub refresh_token: String,
+    pub client_authentication: ClientAuthentication,
+}
+
+impl OAuthConfig {
+    /// Tokens are shared between requests with exactly the same settings.
+    /// Every credential is part of the key, so fixing a wrong secret or
+    /// password fetches a new token. It is hashed so the cache does not hold
+    /// them in the clear a second time.
+    fn cache_key(&self) -> String {
+        let grant = match self.grant {
+            OAuthGrant::ClientCredentials => "client_credentials",
+            OAuthGrant::Password => "password",
+            OAuthGrant::RefreshToken => "refresh_token",
+        };
+        let client_authentication = match self.client_authentication {
+            ClientAuthentication::BasicHeader => "basic",
+            ClientAuthentication::Body => "body",
+        };
+        let key = [
+            grant,
+            &self.token_url,
+            &self.client_id,
+            &self.client_secret,
+            &self.scope,
+            &self.username,
+            &self.password,
+            &self.refresh_token,
+            client_authentication,
+        ]
+        .join("\n");
+        sha256_hex(key.as_bytes())
+    }
+
+    fn grant_params(&self) -> Vec<(&'static str, String)> {
+        match self.grant {
+            OAuthGrant::ClientCredentials => {
+                vec![("grant_type", "client_credentials".to_owned())]
+            }
+            OAuthGrant::Password => vec![
+                ("grant_type", "password".to_owned()),
+                ("username", self.username.clone()),
+                ("password", self.password.clone()),
+            ],
+            OAuthGrant::RefreshToken => refresh_params(&self.refresh_token),
+        }
+    }
+}
+
+fn refresh_params(refresh_token: &str) -> Vec<(&'static str, String)> {
+    vec![
+        ("grant_type", "refresh_token".to_owned()),
+        ("refresh_token", refresh_token.to_owned()),
+    ]
+}
+
+struct Token {
+    access_token: String,
+    expires_at: Option<Instant>,
+    refresh_token: Option<String>,
+}
+
+impl Token {
+    fn is_fresh(&self) -> bool {
+        match self.expires_at {
+            Some(expires_at) => Instant::now() + REFRESH_MARGIN < expires_at,
+            None => true,
+        }
+    }
+}
+
+/// The last token and the last failure for one cache key.
+#[derive(Default)]
+struct CacheSlot {
+    token: Option<Token>,
+    error: Option<String>,
+}
+
+/// What the Auth tab shows about the token for the current settings.
+pub enum TokenState {
+    Missing,
+    Valid { expires_in: Option<Duration> },
+    Expiring,
+    Failed(String),
+}
+
+fn cache() -> MutexGuard<'static, HashMap<String, CacheSlot>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, CacheSlot>>> = OnceLock::new();
+    CACHE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+pub fn token_state(config: &OAuthConfig) -> TokenState {
+    let cache = cache();
+    let Some(slot) = cache.get(&config.cache_key()) else {
+        return TokenState::Missing;
+    };
+    match (&slot.token, &slot.error) {
+        (_, Some(err)) => TokenState::Failed(err.clone()),
+        (Some(token), None) if token.is_fresh() => TokenState::Valid {
+            expires_in: token
+                .expires_at
+                .map(|expires_at| expires_at.saturating_duration_since(Instant::now())),
+        },
+        (Some(_), None) => TokenState::Expiring,
+        (None, None) => TokenState::Missing,
+    }
+}
+
+/// The cached access token, if it is still good to use.
+pub fn cached_access_token(config: &OAuthConfig) -> Option<String> {
+    cache()
+        .get(&config.cache_key())
+        .and_then(|slot| slot.token.as_ref())
+        .filter(|token| token.is_fresh())
+        .map(|token| token.access_token.clone())
+}
+
+/// Returns a fresh access token, from the cache when possible. Expired tokens
+/// are renewed with their refresh token first and with the configured grant
+/// if that fails.
+pub fn access_token(client: &Client, config: &OAuthConfig) -> Result<String, RequestError> {
+    let key = config.cache_key();
+    let refresh_token = match cache().get(&key).and_then(|slot| slot.token.as_ref()) {
+        Some(token) if token.is_fresh() => return Ok(token.access_token.clone()),
+        Some(token) => token.refresh_token.clone(),
+        None => None,
+    };
+
+    let result = match &refresh_token {
+        Some(refresh_token) => request_token(client, config, refresh_params(refresh_token))
+            .or_else(|_| request_token(client, config, config.grant_params())),
+        None => request_token(client, config, config.grant_params()),
+    };
+
+    let mut cache = cache();
+    let slot = cache.entry(key).or_default();
+    match result {
+        Ok(mut token) => {
+            // Servers may leave out the refresh token when it stays the same.
+            if token.refresh_token.is_none() {
+                token.refresh_token = refresh_token;
+            }
+            let access_token = token.access_token.clone();
+            slot.token = Some(token);
+            slot.error = None;
+            Ok(access_token)
+        }
+        Err(err) => {
+            slot.error = Some(err.to_string());
+            Err(err)
+        }
+    }
+}
+
+fn request_token(
+    client: &Client,
+    config: &OAuthConfig,
+    mut params: Vec<(&'static str, String)>,
+) -> Result<Token, RequestError> {
+    if config.token_url.trim().is_empty() {
+        return Err(RequestError::Other(
+            "The OAuth 2.0 token URL is empty".to_owned(),
+        ));
+    }
+    if !config.scope.is_empty() {
+        params.push(("scope", config.scope.clone()));
+    }
+
+    let mut builder = client.post(config.token_url.trim());
+    match config.client_authentication {
+        ClientAuthentication::BasicHeader => {
+            builder = builder.basic_auth(&config.client_id, Some(&config.client_secret));
+        }
+        ClientAuthentication::Body => {
+            params.push(("client_id", config.client_id.clone()));
+            params.push(("client_secret", config.client_secret.clone()));
+        }
+    }
+
+    let started = Instant::now();
+    let response = builder.form(&params).send()?;
+    let status = response.status();
+    let body: Value = serde_json::from_slice(&response.bytes()?).unwrap_or_default();
+
+    if !status.is_success() {
+        let error = [&body["error"], &body["error_description"]]
+            .iter()
+            .filter_map(|value| value.as_str())
+            .collect::<Vec<_>>()
+            .join(": ");
+        return Err(RequestError::Other(format!(
+            "Token request failed with {status} {error}"
+        )));
+    }
+
+    let access_token = body["access_token"]
+        .as_str()
+        .ok_or_else(|| RequestError::Other("The token response has no access_token".to_owned()))?;
+    // Some servers send the lifetime as a string.
+    let expires_in = body["expires_in"]
+        .as_u64()
+        .or_else(|| body["expires_in"].as_str()?.parse().ok());
+
+    Ok(Token {
+        access_token: access_token.to_owned(),
+        expires_at: expires_in.map(|seconds| started + Duration::from_secs(seconds)),
+        refresh_token: body["refresh_token"].as_str().map(str::to_owned),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        thread::{self, JoinHandle},
+    };
+
+    use super::*;
+
+    /// A stand-in token endpoint answering one connection per body in
+    /// `responses` with `200 OK`. Returns its URL and, once joined, the
+    /// requests it received.
+    fn token_endpoint(responses: Vec<&'static str>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/token", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            responses
+                .into_iter()
+                .map(|body| {
+                    let (stream, _) = listener.accept().unwrap();
+                    let mut reader = BufReader::new(stream);
+                    let mut request = String::new();
+                    let mut content_length = 0;
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        if let Some((name, value)) = line.split_once(':') {
+                            if name.eq_ignore_ascii_case("content-length") {
+                                content_length = value.trim().parse().unwrap();
+                            }
+                        }
+                        request.push_str(&line);
+                        if line == "\r\n" {
+                            break;
+                        }
+                    }
+                    let mut request_body = vec![0; content_length];
+                    reader.read_exact(&mut request_body).unwrap();
+                    request.push_str(&String::from_utf8(request_body).unwrap());
+
+                    write!(
+                        reader.get_mut(),
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    )
+                    .unwrap();
+                    request
+                })
+                .collect()
+        });
+        (url, handle)
+    }
+
+    fn fetch(config: &OAuthConfig) -> String {
+        match access_token(&Client::new(), config) {
+            Ok(token) => token,
+            Err(err) => panic!("token request failed: {err}"),
+        }
+    }
+
+    #[test]
+    fn client_credentials_in_body() {
+        let (token_url, endpoint) =
+            token_endpoint(vec![r#"{"access_token":"abc","expires_in":3600}"#]);
+        let config = OAuthConfig {
+            token_url,
+            client_id: "app".to_owned(),
+            client_secret: "s3cret".to_owned(),
+            scope: "read write".to_owned(),
+            client_authentication: ClientAuthentication::Body,
+            ..OAuthConfig::default()
+        };
+
+        assert_eq!(fetch(&config), "abc");
+        let requests = endpoint.join().unwrap();
+        assert!(requests[0].starts_with("POST /token "));
+        assert!(requests[0].ends_with(
+            "\r\n\r\ngrant_type=client_credentials&scope=read+write&client_id=app\
+             &client_secret=s3cret"
+        ));
+        // Served from the cache from now on.
+        assert_eq!(cached_access_token(&config).as_deref(), Some("abc"));
+        assert_eq!(fetch(&config), "abc");
+    }
+
+    #[test]
+    fn password_grant_with_basic_auth() {
+        let (token_url, endpoint) = token_endpoint(vec![r#"{"access_token":"abc"}"#]);
+        let config = OAuthConfig {
+            grant: OAuthGrant::Password,
+            token_url,
+            client_id: "app".to_owned(),
+            client_secret: "s3cret".to_owned(),
+            username: "bob".to_owned(),
+            password: "pw".to_owned(),
+            ..OAuthConfig::default()
+        };
+
+        assert_eq!(fetch(&config), "abc");
+        let request = endpoint.join().unwrap().remove(0);
+        // Base64 of "app:s3cret".
+        assert!(request
+            .to_lowercase()
+            .contains("\r\nauthorization: basic yxbwonmzy3jlda==\r\n"));
+        assert!(request.ends_with("\r\n\r\ngrant_type=password&username=bob&password=pw"));
+        assert!(matches!(
+            token_state(&config),
+            TokenState::Valid { expires_in: None }
+        ));
+    }
+
+    #[test]
+    fn expired_token_is_refreshed() {
+        let (token_url, endpoint) = token_endpoint(vec![
+            // Already within the refresh margin.
+            r#"{"access_token":"first","expires_in":"10","refresh_token":"r1"}"#,
+            r#"{"access_token":"second","expires_in":3600}"#,
+        ]);
+        let config = OAuthConfig {
+            token_url,
+            client_id: "app".to_owned(),
+            ..OAuthConfig::default()
+        };
+
+        assert_eq!(fetch(&config), "first");
+        assert!(matches!(token_state(&config), TokenState::Expiring));
+        assert_eq!(cached_access_token(&config), None);
+        assert_eq!(fetch(&config), "second");
+
+        let requests = endpoint.join().unwrap();
+        assert!(requests[1].ends_with("\r\n\r\ngrant_type=refresh_token&refresh_token=r1"));
+        // The refresh token is kept when the server does not send a new one.
+        let cache = cache();
+        let token = cache[&config.cache_key()].token.as_ref().unwrap();
+        assert_eq!(token.refresh_token.as_deref(), Some("r1"));
+    }
+
+    #[test]
+    fn changed_credentials_get_their_own_token() {
+        let config = OAuthConfig {
+            token_url: "http://127.0.0.1/token".to_owned(),
+            client_secret: "wrong".to_owned(),
+            ..OAuthConfig::default()
+        };
+        let fixed = OAuthConfig {
+            client_secret: "right".to_owned(),
+            ..config.clone()
+        };
+        assert_ne!(config.cache_key(), fixed.cache_key());
+
+        let fixed_password = OAuthConfig {
+            password: "right".to_owned(),
+            ..config.clone()
+        };
+        assert_ne!(config.cache_key(), fixed_password.cache_key());
+    }
+}
@@ -0,0 +1,73 @@
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+
+/// Characters encoded in query keys and values. Braces are left alone so
+/// `{{name}}` placeholders stay readable in the URL bar.
+const QUERY_COMPONENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'&')
+    .add(b'+')
+    .add(b'<')
+    .add(b'=')
+    .add(b'>')
+    .add(b'`');
+
+/// Splits a URL as typed into the part before the query, the query and the
+/// fragment. It may contain placeholders, so it is not parsed as a URL.
+pub fn split_url(url: &str) -> (&str, Option<&str>, Option<&str>) {
+    let (url, fragment) = match url.split_once('#') {
+        Some((url, fragment)) => (url, Some(fragment)),
+        None => (url, None),
+    };
+    match url.split_once('?') {
+        Some((base, query)) => (base, Some(query), fragment),
+        None => (url, None, fragment),
+    }
+}
+
+/// Decodes `a=1&b=two%20words` into key/value pairs. `+` stands for a space,
+/// as in forms.
+pub fn parse(query: &str) -> Vec<(String, String)> {
+    let decode = |text: &str| {
+        percent_decode_str(&text.replace('+', " "))
+            .decode_utf8_lossy()
+            .into_owned()
+    };
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(key), decode(value))
+        })
+        .collect()
+}
+
+/// The URL with its query replaced by `pairs`, keeping the fragment.
+pub fn with_query<'p>(url: &str, pairs: impl Iterator<Item = (&'p str, &'p str)>) -> String {
+    let (base, _, fragment) = split_url(url);
+    let encode = |text: &str| utf8_percent_encode(text, QUERY_COMPONENT).to_string();
+    let query = pairs
+        .map(|(key, value)| match value {
+            "" => encode(key),
+            value => format!("{}={}", encode(key), encode(value)),
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let mut url = base.to_owned();
+    if !query.is_empty() {
+        url = format!("{url}?{query}");
+    }
+    if let Some(fragment) = fragment {
+        url = format!("{url}#{fragment}");
+    }
+    url
+}
+
+/// The URL with its query left out, keeping the fragment.
+pub fn without_query(url: &str) -> String {
+    with_query(url, std::iter::empty())
+}
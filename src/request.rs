@@ -0,0 +1,577 @@
+use std::{
+    error::Error,
+    fmt,
+    io::{self, Read},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use reqwest::{
+    blocking::{Client, Request},
+    header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, SET_COOKIE},
+    Method, StatusCode, Version,
+};
+use serde_json::Value;
+
+use crate::{
+    app::{App, RequestType},
+    auth::Auth,
+    collection::SavedRequest,
+    digest, environment, graphql, json, oauth, query,
+    request_body::{self, BodyType, Part, PreparedBody},
+};
+
+/// Everything needed to perform a request, copied out of the editor widgets so
+/// it can be moved onto a worker thread.
+pub struct PreparedRequest {
+    pub method: Method,
+    pub url: String,
+    pub query_params: Vec<(String, String)>,
+    pub headers: HeaderMap,
+    /// Left out entirely when the body type is None.
+    pub body: Option<PreparedBody>,
+    pub auth: Auth,
+    /// Stops the upload and the download once set.
+    pub cancel: CancelFlag,
+}
+
+impl PreparedRequest {
+    /// Copies the request out of the editor widgets, resolving `{{name}}`
+    /// placeholders against the active environment.
+    pub fn from_app(app: &App) -> Result<Self, RequestError> {
+        Self::prepare(app, None)
+    }
+
+    /// The introspection query for the GraphQL schema, sent as a POST to the
+    /// URL with the query, headers and auth of the request.
+    pub fn introspection(app: &App) -> Result<Self, RequestError> {
+        let mut request = Self::prepare(app, Some(graphql::INTROSPECTION_QUERY))?;
+        request.method = Method::POST;
+        Ok(request)
+    }
+
+    /// Sends `graphql_query` instead of the GraphQL editors, if it is set.
+    fn prepare(app: &App, graphql_query: Option<&str>) -> Result<Self, RequestError> {
+        let environment = app.active_environment();
+        let mut unresolved = Vec::new();
+        let mut substitute =
+            |text: &str| environment::substitute(text, environment, &mut unresolved);
+
+        let method = match &app.selected_request_type {
+            RequestType::Get => Method::GET,
+            RequestType::Post => Method::POST,
+            RequestType::Put => Method::PUT,
+            RequestType::Patch => Method::PATCH,
+            RequestType::Delete => Method::DELETE,
+            RequestType::Head => Method::HEAD,
+            RequestType::Options => Method::OPTIONS,
+            RequestType::Trace => Method::TRACE,
+            RequestType::Connect => Method::CONNECT,
+            RequestType::Custom(method) => Method::from_bytes(method.as_bytes())
+                .map_err(|_| RequestError::Other(format!("'{method}' is not a valid method")))?,
+        };
+
+        let query_params = app
+            .widgets
+            .query_editor
+            .enabled_pairs()
+            .map(|(key, value)| (substitute(key), substitute(value)))
+            .collect();
+
+        let header_pairs: Vec<(String, String)> = app
+            .widgets
+            .headers_editor
+            .enabled_pairs()
+            .map(|(key, value)| (substitute(key), substitute(value)))
+            .collect();
+
+        let body = match app.body_type {
+            BodyType::None => None,
+            BodyType::Raw | BodyType::Json | BodyType::Xml => Some(PreparedBody::Text(substitute(
+                &app.widgets.body_text_area.lines().join("\n"),
+            ))),
+            BodyType::Form => Some(PreparedBody::Form(
+                app.widgets
+                    .form_editor
+                    .enabled_pairs()
+                    .map(|(key, value)| (substitute(key), substitute(value)))
+                    .collect(),
+            )),
+            BodyType::Multipart => Some(PreparedBody::Multipart(
+                app.widgets
+                    .multipart_editor
+                    .enabled_parts()
+                    .map(|(name, value, file)| Part {
+                        name: substitute(name),
+                        value: substitute(value),
+                        file,
+                    })
+                    .collect(),
+            )),
+            BodyType::Binary => Some(PreparedBody::File(substitute(
+                app.widgets.body_file_input.text().trim(),
+            ))),
+            // Assembled below, once the variables are known to be resolved.
+            BodyType::GraphQl => None,
+        };
+        let graphql = match graphql_query {
+            Some(query) => Some((query.to_owned(), String::new(), None)),
+            None if app.body_type == BodyType::GraphQl => {
+                let query = app.widgets.graphql_query_area.lines().join("\n");
+                let operation_name = graphql::operation_name(&query, app.graphql_cursor());
+                Some((
+                    substitute(&query),
+                    substitute(&app.widgets.graphql_variables_area.lines().join("\n")),
+                    operation_name,
+                ))
+            }
+            None => None,
+        };
+        // The Query tab holds the query string of the URL, decoded.
+        let url = substitute(&query::without_query(app.widgets.url_text_input.text()));
+        let auth = app.widgets.auth_editor.to_auth().map_text(&mut substitute);
+
+        if !unresolved.is_empty() {
+            return Err(RequestError::UnresolvedVariables(unresolved));
+        }
+        let body = match graphql {
+            Some((query, variables, operation_name)) => Some(PreparedBody::Text(
+                graphql::request_body(&query, &variables, operation_name)
+                    .map_err(RequestError::Other)?,
+            )),
+            None => body,
+        };
+
+        let mut headers = HeaderMap::new();
+        for (key, value) in header_pairs {
+            let name = HeaderName::from_bytes(key.trim().as_bytes()).map_err(|_| {
+                RequestError::Other(format!("'{}' is not a valid header name", key.trim()))
+            })?;
+            let value = HeaderValue::from_str(value.trim()).map_err(|_| {
+                RequestError::Other(format!("The {name} header contains invalid characters"))
+            })?;
+            headers.append(name, value);
+        }
+        // A Content-Type from the Headers tab wins over the body type's.
+        let content_type = match &body {
+            Some(PreparedBody::File(path)) => Some(request_body::guess_content_type(path)),
+            _ => app.body_type.content_type().map(str::to_owned),
+        };
+        if !headers.contains_key(CONTENT_TYPE) {
+            if let Some(value) = content_type.and_then(|value| HeaderValue::from_str(&value).ok()) {
+                headers.insert(CONTENT_TYPE, value);
+            }
+        }
+
+        Ok(Self {
+            method,
+            url,
+            query_params,
+            headers,
+            body,
+            auth,
+            cancel: CancelFlag::default(),
+        })
+    }
+
+    /// Builds the request exactly as it goes over the wire, with the query
+    /// parameters encoded into the URL.
+    pub fn build(self, client: &Client) -> Result<Request, RequestError> {
+        let builder = client
+            .request(self.method, self.url)
+            .query(&self.query_params);
+        let builder = match self.body {
+            None => builder,
+            Some(PreparedBody::Text(text)) => builder.body(text),
+            Some(PreparedBody::Form(pairs)) => builder.form(&pairs),
+            Some(PreparedBody::Multipart(parts)) => {
+                builder.multipart(request_body::multipart_form(parts, &self.cancel)?)
+            }
+            Some(PreparedBody::File(path)) => {
+                builder.body(request_body::file_body(&path, &self.cancel)?)
+            }
+        };
+        // After the body, so a Content-Type from the Headers tab wins over
+        // the one reqwest sets for forms.
+        let mut request = builder.headers(self.headers).build()?;
+        self.auth.apply(&mut request)?;
+        Ok(request)
+    }
+}
+
+/// Shared between the event loop and the worker thread, so cancelling a
+/// request also stops its transfer instead of only no longer waiting for it.
+#[derive(Clone, Default)]
+pub struct CancelFlag(Arc<AtomicBool>);
+
+impl CancelFlag {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A body that fails its next read once the request is cancelled. Failing the
+/// read makes reqwest drop the connection, whichever way the data flows.
+pub struct Cancellable<R> {
+    inner: R,
+    cancel: CancelFlag,
+}
+
+impl<R> Cancellable<R> {
+    pub fn new(inner: R, cancel: &CancelFlag) -> Self {
+        Self {
+            inner,
+            cancel: cancel.clone(),
+        }
+    }
+}
+
+impl<R: Read> Read for Cancellable<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.cancel.is_cancelled() {
+            return Err(io::Error::other("request cancelled"));
+        }
+        self.inner.read(buf)
+    }
+}
+
+/// Why a request failed, grouped by what the user can do about it.
+pub enum RequestError {
+    InvalidUrl(String),
+    Connect(String),
+    Timeout,
+    Tls(String),
+    Decode(String),
+    UnresolvedVariables(Vec<String>),
+    Other(String),
+}
+
+impl RequestError {
+    /// A short summary for the response status line.
+    pub fn title(&self) -> &'static str {
+        match self {
+            RequestError::InvalidUrl(_) => "Invalid URL",
+            RequestError::Connect(_) => "Connection failed",
+            RequestError::Timeout => "Request timed out",
+            RequestError::Tls(_) => "TLS error",
+            RequestError::Decode(_) => "Could not decode response body",
+            RequestError::UnresolvedVariables(_) => "Unresolved variables",
+            RequestError::Other(_) => "Request failed",
+        }
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestError::InvalidUrl(message)
+            | RequestError::Connect(message)
+            | RequestError::Tls(message)
+            | RequestError::Decode(message)
+            | RequestError::Other(message) => write!(f, "{message}"),
+            RequestError::UnresolvedVariables(names) => write!(
+                f,
+                "Not defined in the active environment: {}",
+                names
+                    .iter()
+                    .map(|name| format!("{{{{{name}}}}}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            RequestError::Timeout => write!(f, "{}", self.title()),
+        }
+    }
+}
+
+impl From<reqwest::Error> for RequestError {
+    fn from(err: reqwest::Error) -> Self {
+        // reqwest's own message is terse ("error sending request for url"), the
+        // useful part is usually further down the source chain.
+        let mut message = err.to_string();
+        let mut source = err.source();
+        while let Some(cause) = source {
+            let cause_message = cause.to_string();
+            if !message.contains(&cause_message) {
+                message = format!("{message}: {cause_message}");
+            }
+            source = cause.source();
+        }
+
+        if err.is_builder() {
+            RequestError::InvalidUrl(message)
+        } else if err.is_timeout() {
+            RequestError::Timeout
+        } else if err.is_connect() {
+            let lowercase = message.to_lowercase();
+            if lowercase.contains("tls")
+                || lowercase.contains("ssl")
+                || lowercase.contains("certificate")
+            {
+                RequestError::Tls(message)
+            } else {
+                RequestError::Connect(message)
+            }
+        } else if err.is_decode() || err.is_body() {
+            RequestError::Decode(message)
+        } else {
+            RequestError::Other(message)
+        }
+    }
+}
+
+pub struct Response {
+    pub status: StatusCode,
+    pub version: Version,
+    pub headers: HeaderMap,
+    pub body: String,
+    /// The parsed body, if it is valid JSON.
+    pub json: Option<Value>,
+    /// The body re-indented, if it is valid JSON.
+    pub pretty_body: Option<String>,
+    /// Size of the body as received, before any lossy UTF-8 conversion.
+    pub size: usize,
+    /// Time from sending the request until the whole body was read.
+    pub elapsed: Duration,
+    /// The 401 answered before this response, for Digest auth.
+    pub challenge: Option<AuthChallenge>,
+}
+
+/// The first leg of a request that had to answer an authentication challenge.
+pub struct AuthChallenge {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub elapsed: Duration,
+}
+
+impl Response {
+    pub fn new(
+        status: StatusCode,
+        version: Version,
+        headers: HeaderMap,
+        body: String,
+        size: usize,
+        elapsed: Duration,
+    ) -> Self {
+        let json: Option<Value> = serde_json::from_str(&body).ok();
+        let pretty_body = json.as_ref().map(|_| json::pretty_print(&body));
+        Self {
+            status,
+            version,
+            headers,
+            body,
+            json,
+            pretty_body,
+            size,
+            elapsed,
+            challenge: None,
+        }
+    }
+
+    /// Cookies set by the server as `(name, value, attributes)`.
+    pub fn cookies(&self) -> Vec<(String, String, String)> {
+        self.headers
+            .get_all(SET_COOKIE)
+            .iter()
+            .filter_map(|header_value| {
+                let cookie = header_value.to_str().ok()?;
+                let (pair, attributes) = cookie.split_once(';').unwrap_or((cookie, ""));
+                let (name, value) = pair.split_once('=')?;
+                Some((
+                    name.trim().to_owned(),
+                    value.trim().to_owned(),
+                    attributes.trim().to_owned(),
+                ))
+            })
+            .collect()
+    }
+}
+
+pub fn send_request(mut request: PreparedRequest) -> Result<Response, RequestError> {
+    let client = Client::new();
+
+    let cancel = request.cancel.clone();
+    if let Auth::OAuth2(config) = &request.auth {
+        let (client, config) = (client.clone(), config.clone());
+        let token = unless_cancelled(&cancel, move || oauth::access_token(&client, &config))?;
+        request.auth = Auth::Bearer { token };
+    }
+    let digest_credentials = match &request.auth {
+        Auth::Digest { username, password } => Some((username.clone(), password.clone())),
+        _ => None,
+    };
+    let request = request.build(&client)?;
+
+    let started = Instant::now();
+    let body_cancel = cancel.clone();
+    let (response, bytes, challenge) = unless_cancelled(&cancel, move || {
+        let (response, challenge) = match digest_credentials {
+            Some((username, password)) => digest::execute(&client, request, &username, &password)?,
+            None => (client.execute(request)?, None),
+        };
+        let mut bytes = Vec::new();
+        let mut body = Cancellable::new(response, &body_cancel);
+        body.read_to_end(&mut bytes)
+            .map_err(|err| match err.into_inner() {
+                // Errors from the connection itself, like a timeout.
+                Some(err) => match err.downcast::<reqwest::Error>() {
+                    Ok(err) => RequestError::from(*err),
+                    Err(err) => RequestError::Other(err.to_string()),
+                },
+                None => RequestError::Other("the response body could not be read".to_owned()),
+            })?;
+        Ok((body.inner, bytes, challenge))
+    })?;
+    let status = response.status();
+    let version = response.version();
+    let headers = response.headers().clone();
+    let elapsed = started.elapsed();
+
+    let mut response = Response::new(
+        status,
+        version,
+        headers,
+        String::from_utf8_lossy(&bytes).into_owned(),
+        bytes.len(),
+        elapsed,
+    );
+    response.challenge = challenge;
+    Ok(response)
+}
+
+/// Runs the part of a request that waits on the network on a thread of its
+/// own, so a cancelled request returns at once even while the server has not
+/// answered yet. reqwest cannot interrupt that wait: the abandoned thread and
+/// its connection end when the server answers or the client times out, and
+/// the result is dropped.
+fn unless_cancelled<T: Send + 'static>(
+    cancel: &CancelFlag,
+    exchange: impl FnOnce() -> Result<T, RequestError> + Send + 'static,
+) -> Result<T, RequestError> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(exchange());
+    });
+    loop {
+        match receiver.recv_timeout(Duration::from_millis(50)) {
+            Ok(result) => return result,
+            Err(RecvTimeoutError::Timeout) if !cancel.is_cancelled() => {}
+            Err(RecvTimeoutError::Timeout) => {
+                return Err(RequestError::Other("Request cancelled".to_owned()))
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(RequestError::Other(
+                    "the request thread stopped unexpectedly".to_owned(),
+                ))
+            }
+        }
+    }
+}
+
+/// A request running on a worker thread. The result is delivered through a
+/// channel so the event loop can keep drawing while waiting.
+pub struct PendingRequest {
+    receiver: Receiver<Result<Response, RequestError>>,
+    cancel: CancelFlag,
+    pub started: Instant,
+    /// Method and URL as sent, for the history.
+    pub method: String,
+    pub url: String,
+    /// The editor contents the request was sent from, for the history.
+    pub saved_request: SavedRequest,
+}
+
+impl PendingRequest {
+    pub fn spawn(request: PreparedRequest, saved_request: SavedRequest) -> Self {
+        let method = request.method.to_string();
+        let url = match reqwest::Url::parse(&request.url) {
+            Ok(mut url) if !request.query_params.is_empty() => {
+                url.query_pairs_mut().extend_pairs(&request.query_params);
+                url.to_string()
+            }
+            _ => request.url.clone(),
+        };
+        let cancel = request.cancel.clone();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            // The receiver is gone if the request was cancelled or the app exited
+            // while the request was running.
+            let _ = sender.send(send_request(request));
+        });
+
+        Self {
+            receiver,
+            cancel,
+            started: Instant::now(),
+            method,
+            url,
+            saved_request,
+        }
+    }
+
+    /// Makes the worker give up on the request. A body being sent or received
+    /// stops at its next chunk. A request still waiting for the server's
+    /// answer is abandoned, see `unless_cancelled`.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Returns the result once the worker has finished, without blocking.
+    pub fn poll(&self) -> Option<Result<Response, RequestError>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(RequestError::Other(
+                "the request worker stopped unexpectedly".to_owned(),
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn cancel_does_not_wait_for_the_server() {
+        // Accepts the connection and never answers.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            let _connection = listener.accept();
+            thread::sleep(Duration::from_secs(10));
+        });
+
+        let request = PreparedRequest {
+            method: Method::GET,
+            url,
+            query_params: Vec::new(),
+            headers: HeaderMap::new(),
+            body: None,
+            auth: Auth::None,
+            cancel: CancelFlag::default(),
+        };
+        let cancel = request.cancel.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            cancel.cancel();
+        });
+
+        let started = Instant::now();
+        match send_request(request) {
+            Ok(_) => panic!("the server never answers"),
+            Err(err) => assert_eq!(err.to_string(), "Request cancelled"),
+        }
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+}
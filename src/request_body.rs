@@ -0,0 +1,295 @@
+use std::{fs::File, path::Path};
+
+use reqwest::blocking::{multipart, Body};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    environment::placeholder_regex,
+    request::{CancelFlag, Cancellable, RequestError},
+};
+
+/// What the Body tab sends.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BodyType {
+    /// No body at all.
+    #[default]
+    None,
+    /// The text as typed, without a `Content-Type`.
+    Raw,
+    Json,
+    Xml,
+    /// `application/x-www-form-urlencoded` rows.
+    Form,
+    /// `multipart/form-data` rows, with text or files.
+    Multipart,
+    /// A file from disk, sent as it is.
+    Binary,
+    /// A query and its variables, sent as `{"query", "variables", "operationName"}`.
+    #[serde(rename = "graphql")]
+    GraphQl,
+}
+
+impl BodyType {
+    pub const ALL: [BodyType; 8] = [
+        BodyType::None,
+        BodyType::Raw,
+        BodyType::Json,
+        BodyType::Xml,
+        BodyType::Form,
+        BodyType::Multipart,
+        BodyType::Binary,
+        BodyType::GraphQl,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BodyType::None => "None",
+            BodyType::Raw => "Raw",
+            BodyType::Json => "JSON",
+            BodyType::Xml => "XML",
+            BodyType::Form => "Form URL-encoded",
+            BodyType::Multipart => "Multipart form",
+            BodyType::Binary => "Binary file",
+            BodyType::GraphQl => "GraphQL",
+        }
+    }
+
+    /// Sent unless the Headers tab sets a `Content-Type` of its own. Forms
+    /// get theirs from reqwest, multipart ones with the boundary, and files
+    /// from their extension.
+    pub fn content_type(self) -> Option<&'static str> {
+        match self {
+            BodyType::None
+            | BodyType::Raw
+            | BodyType::Form
+            | BodyType::Multipart
+            | BodyType::Binary => None,
+            BodyType::Json | BodyType::GraphQl => Some("application/json"),
+            BodyType::Xml => Some("application/xml"),
+        }
+    }
+
+    /// The next or previous type, for cycling through them with the arrow
+    /// keys.
+    pub fn cycle(self, forward: bool) -> BodyType {
+        let index = BodyType::ALL
+            .iter()
+            .position(|body_type| *body_type == self)
+            .unwrap_or(0);
+        let len = BodyType::ALL.len();
+        let index = if forward {
+            (index + 1) % len
+        } else {
+            (index + len - 1) % len
+        };
+        BodyType::ALL[index]
+    }
+}
+
+/// A body about to be sent, with placeholders resolved.
+#[derive(Clone)]
+pub enum PreparedBody {
+    Text(String),
+    Form(Vec<(String, String)>),
+    Multipart(Vec<Part>),
+    /// The path of the file to send.
+    File(String),
+}
+
+/// A field of a multipart form.
+#[derive(Clone)]
+pub struct Part {
+    pub name: String,
+    /// The text, or the path of the file to upload.
+    pub value: String,
+    pub file: bool,
+}
+
+/// Builds the multipart form, opening the files to stream them. Their
+/// `Content-Type` is guessed from the extension.
+pub fn multipart_form(
+    parts: Vec<Part>,
+    cancel: &CancelFlag,
+) -> Result<multipart::Form, RequestError> {
+    parts
+        .into_iter()
+        .try_fold(multipart::Form::new(), |form, part| {
+            if !part.file {
+                return Ok(form.text(part.name, part.value));
+            }
+            let path = &part.value;
+            let open = || -> Result<multipart::Part, Box<dyn std::error::Error>> {
+                let file = File::open(path)?;
+                let len = file.metadata()?.len();
+                let file_name = Path::new(path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                Ok(
+                    multipart::Part::reader_with_length(Cancellable::new(file, cancel), len)
+                        .file_name(file_name)
+                        .mime_str(&guess_content_type(path))?,
+                )
+            };
+            open()
+                .map(|file_part| form.part(part.name, file_part))
+                .map_err(|err| RequestError::Other(format!("{path}: {err}")))
+        })
+}
+
+/// Opens the file to stream it as the body, so large files are never read
+/// into memory.
+pub fn file_body(path: &str, cancel: &CancelFlag) -> Result<Body, RequestError> {
+    if path.trim().is_empty() {
+        return Err(RequestError::Other(
+            "Choose a file to send as the body".to_owned(),
+        ));
+    }
+    let open = || {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        Ok(Body::sized(Cancellable::new(file, cancel), len))
+    };
+    open().map_err(|err: std::io::Error| RequestError::Other(format!("{path}: {err}")))
+}
+
+/// The `Content-Type` for a file, from its extension.
+pub fn guess_content_type(path: &str) -> String {
+    mime_guess::from_path(Path::new(path))
+        .first_or_octet_stream()
+        .to_string()
+}
+
+/// Requests saved before there were body types sent their body as typed.
+pub fn legacy_body_type() -> BodyType {
+    BodyType::Raw
+}
+
+/// Why a JSON body does not parse. Placeholders outside of strings, like
+/// `"id": {{id}}`, count as values. A blank body is not an error.
+pub fn json_error(text: &str) -> Option<serde_json::Error> {
+    if text.trim().is_empty() {
+        return None;
+    }
+    // Padded to the length of the placeholder so error columns stay right.
+    let masked = mask_placeholders(text, |placeholder| {
+        format!("{:<1$}", "null", placeholder.chars().count())
+    });
+    serde_json::from_str::<Value>(&masked).err()
+}
+
+/// Pretty-prints a JSON body the way `serde_json` does, keeping its
+/// placeholders. It is re-indented token by token instead of going through a
+/// `Value`, so numbers like `1.10` or `1e3` and string escapes stay exactly as
+/// they were typed.
+pub fn format_json(text: &str) -> Result<String, serde_json::Error> {
+    if let Some(err) = json_error(text) {
+        return Err(err);
+    }
+
+    let mut pretty = String::with_capacity(text.len());
+    let mut depth = 0;
+    let new_line = |pretty: &mut String, depth: usize| {
+        pretty.push('\n');
+        pretty.push_str(&"  ".repeat(depth));
+    };
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if let Some(placeholder) = leading_placeholder(rest) {
+            pretty.push_str(placeholder);
+            rest = &rest[placeholder.len()..];
+            continue;
+        }
+
+        match c {
+            '"' => {
+                let string = leading_string(rest);
+                pretty.push_str(string);
+                rest = &rest[string.len()..];
+                continue;
+            }
+            '{' | '[' => {
+                let close = if c == '{' { '}' } else { ']' };
+                let inside = rest[1..].trim_start();
+                if inside.starts_with(close) {
+                    pretty.push(c);
+                    pretty.push(close);
+                    rest = &inside[1..];
+                    continue;
+                }
+                depth += 1;
+                pretty.push(c);
+                new_line(&mut pretty, depth);
+            }
+            '}' | ']' => {
+                depth -= 1;
+                new_line(&mut pretty, depth);
+                pretty.push(c);
+            }
+            ',' => {
+                pretty.push(c);
+                new_line(&mut pretty, depth);
+            }
+            ':' => pretty.push_str(": "),
+            c if c.is_whitespace() => {}
+            c => pretty.push(c),
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    Ok(pretty)
+}
+
+/// The `{{name}}` placeholder `text` starts with.
+fn leading_placeholder(text: &str) -> Option<&str> {
+    if !text.starts_with("{{") {
+        return None;
+    }
+    placeholder_regex()
+        .find(text)
+        .filter(|placeholder| placeholder.start() == 0)
+        .map(|placeholder| placeholder.as_str())
+}
+
+/// The JSON string `text` starts with, quotes included.
+fn leading_string(text: &str) -> &str {
+    let mut escaped = false;
+    for (index, c) in text.char_indices().skip(1) {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return &text[..index + 1],
+            _ => escaped = false,
+        }
+    }
+    text
+}
+
+/// Replaces the placeholders that stand for whole JSON values, the ones
+/// outside of strings. Those inside strings are valid JSON already.
+fn mask_placeholders(text: &str, mut mask: impl FnMut(&str) -> String) -> String {
+    let mut masked = String::with_capacity(text.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if !in_string {
+            if let Some(placeholder) = leading_placeholder(rest) {
+                masked.push_str(&mask(placeholder));
+                rest = &rest[placeholder.len()..];
+                continue;
+            }
+        }
+
+        if escaped {
+            escaped = false;
+        } else if in_string && c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            in_string = !in_string;
+        }
+        masked.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    masked
+}
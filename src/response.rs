@@ -0,0 +1,469 @@
+use std::cmp::min;
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Paragraph, Row, Scrollbar, ScrollbarOrientation, ScrollbarState, Table,
+        Tabs, Wrap,
+    },
+    Frame,
+};
+use reqwest::{header::HeaderMap, StatusCode};
+
+use crate::{
+    app::{is_within_block, ResponseState, SelectionState},
+    filter, graphql, json,
+    request::Response,
+    request_body::BodyType,
+    App,
+};
+
+const SPINNER_FRAMES: [&str; 8] = ["⣾", "⣽", "⣻", "⢿", "⡿", "⣟", "⣯", "⣷"];
+
+pub fn render_response_side(f: &mut Frame, app: &mut App, chunk: Rect) {
+    let blocks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Min(0),
+        ])
+        .split(chunk);
+
+    let tab_block = blocks[0];
+    let status_block = blocks[1];
+    let mut content_block = blocks[2];
+
+    // The filter and search bars only apply to the Body tab.
+    let mut filter_block = None;
+    let mut search_block = None;
+    if app.response_tabs.index == 0 {
+        let search_height = if app.search.visible { 3 } else { 0 };
+        let blocks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Min(0),
+                Constraint::Length(search_height),
+            ])
+            .split(content_block);
+        filter_block = Some(blocks[0]);
+        content_block = blocks[1];
+        if app.search.visible {
+            search_block = Some(blocks[2]);
+        }
+    }
+
+    // GraphQL servers answer 200 with the errors next to the data, they get
+    // a box of their own above the body.
+    let graphql_errors = graphql_errors(app);
+    if app.response_tabs.index == 0 && !graphql_errors.is_empty() {
+        // Long messages wrap, the box grows with them up to a few lines.
+        let width = content_block.width.saturating_sub(2).max(1) as usize;
+        let error_lines: usize = graphql_errors
+            .iter()
+            .map(|error| (error.chars().count() + 2).div_ceil(width))
+            .sum();
+        let errors_height = min(error_lines, 6) as u16 + 2;
+        let blocks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(errors_height), Constraint::Min(0)])
+            .split(content_block);
+        render_graphql_errors(f, &graphql_errors, blocks[0]);
+        content_block = blocks[1];
+    }
+
+    if let Some(position) = app.last_mouse_down_event {
+        if is_within_block(&tab_block, position) {
+            app.selected_input = SelectionState::ResponseTabs;
+            app.last_mouse_down_event = None;
+        } else if filter_block.is_some_and(|block| is_within_block(&block, position)) {
+            app.selected_input = SelectionState::ResponseFilter;
+            app.last_mouse_down_event = None;
+        } else if is_within_block(&content_block, position) {
+            app.selected_input = SelectionState::ResponseBody;
+            app.last_mouse_down_event = None;
+        }
+    }
+
+    if let Some(filter_block) = filter_block {
+        render_filter_bar(f, app, filter_block);
+    }
+
+    if app.response_area != content_block {
+        app.response_area = content_block;
+        app.scroll_response_to(app.response_scroll);
+    }
+
+    f.render_widget(
+        Paragraph::new(status_line(app)).block(Block::default().borders(Borders::ALL)),
+        status_block,
+    );
+
+    let mut tab_block_style = Block::default().borders(Borders::ALL);
+    if let SelectionState::ResponseTabs = app.selected_input {
+        tab_block_style = tab_block_style.border_style(Style::new().red());
+    }
+    let tabs = Tabs::new(app.response_tabs.items.clone())
+        .block(tab_block_style)
+        .select(app.response_tabs.index)
+        .highlight_style(
+            Style::default()
+                .bg(Color::White)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+        );
+    f.render_widget(tabs, tab_block);
+
+    let mut block = Block::default().borders(Borders::ALL);
+    if let SelectionState::ResponseBody = app.selected_input {
+        block = block.border_style(Style::new().red());
+    }
+    match &app.response {
+        ResponseState::Received(response) if app.pending_request.is_none() => {
+            // "Body", "Headers", "Cookies"
+            match app.response_tabs.index {
+                0 => {
+                    let block = match (&response.pretty_body, app.pretty_response) {
+                        (Some(_), true) => block.title("JSON (pretty)"),
+                        (Some(_), false) => block.title("JSON (raw)"),
+                        (None, _) => block,
+                    };
+                    render_body_lines(f, app, block, content_block);
+                    if let Some(search_block) = search_block {
+                        render_search_bar(f, app, search_block);
+                    }
+                }
+                1 => f.render_widget(headers_table(response).block(block), content_block),
+                2 => f.render_widget(cookies_table(response).block(block), content_block),
+                _ => unreachable!("Index out of bounds for response tabs."),
+            }
+        }
+        ResponseState::Failed(err) if app.pending_request.is_none() => f.render_widget(
+            Paragraph::new(err.to_string())
+                .wrap(Wrap { trim: false })
+                .block(block),
+            content_block,
+        ),
+        _ => f.render_widget(block, content_block),
+    }
+}
+
+/// The `errors` of the response to a GraphQL request.
+fn graphql_errors(app: &App) -> Vec<String> {
+    match &app.response {
+        ResponseState::Received(response)
+            if app.body_type == BodyType::GraphQl && app.pending_request.is_none() =>
+        {
+            response.json.as_ref().map(graphql::errors).unwrap_or_default()
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn render_graphql_errors(f: &mut Frame, errors: &[String], chunk: Rect) {
+    let lines: Vec<Line> = errors
+        .iter()
+        .map(|error| Line::raw(format!("✗ {error}")))
+        .collect();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::new().red())
+        .title(format!("GraphQL errors ({})", errors.len()));
+    f.render_widget(
+        Paragraph::new(lines)
+            .style(Style::default().fg(Color::Red))
+            .wrap(Wrap { trim: false })
+            .block(block),
+        chunk,
+    );
+}
+
+fn render_body_lines(f: &mut Frame, app: &App, block: Block, chunk: Rect) {
+    let total_lines = app.response_lines.len();
+    let page_height = app.response_page_height();
+    let first_line = app.response_scroll;
+    let last_line = min(first_line + page_height, total_lines);
+
+    let mut block = if total_lines > 0 {
+        block.title(
+            Title::from(format!(
+                " {}-{}/{} ",
+                first_line + 1,
+                last_line,
+                total_lines
+            ))
+            .alignment(Alignment::Right)
+            .position(Position::Bottom),
+        )
+    } else {
+        block
+    };
+    if app.search.visible {
+        block = block
+            .title(Title::from(format!(" {} ", app.search.summary())).alignment(Alignment::Right));
+    }
+
+    // Only the visible lines are handed to the paragraph so large bodies stay cheap to draw.
+    let lines: Vec<Line> = app.response_lines[first_line..last_line]
+        .iter()
+        .enumerate()
+        .map(|(offset, line)| app.search.highlight_line(line, first_line + offset))
+        .collect();
+    let paragraph = Paragraph::new(lines).block(block);
+    f.render_widget(paragraph, chunk);
+
+    if total_lines > page_height {
+        let mut scrollbar_state = ScrollbarState::new(total_lines.saturating_sub(page_height))
+            .position(first_line)
+            .viewport_content_length(page_height);
+        f.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight),
+            chunk.inner(&Margin {
+                vertical: 1,
+                horizontal: 0,
+            }),
+            &mut scrollbar_state,
+        );
+    }
+}
+
+fn render_filter_bar(f: &mut Frame, app: &App, chunk: Rect) {
+    let mut block = Block::default().borders(Borders::ALL);
+    if let Some(err) = &app.response_filter_error {
+        block = block.title(
+            Title::from(Line::styled(
+                format!(" ✗ {err} "),
+                Style::default().fg(Color::Red),
+            ))
+            .alignment(Alignment::Right),
+        );
+    }
+    if let SelectionState::ResponseFilter = app.selected_input {
+        block = block.border_style(Style::new().red());
+        f.set_cursor(
+            chunk.x + app.response_filter.cursor_position() as u16 + 1,
+            chunk.y + 1,
+        );
+    }
+    f.render_widget(app.response_filter.clone().block(block), chunk);
+}
+
+fn render_search_bar(f: &mut Frame, app: &App, chunk: Rect) {
+    let option_style = |enabled: bool| {
+        if enabled {
+            Style::default().fg(Color::Black).bg(Color::White)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        }
+    };
+    let options = Line::from(vec![
+        Span::styled(" Aa ", option_style(app.search.case_sensitive)),
+        Span::raw(" "),
+        Span::styled(" .* ", option_style(app.search.regex)),
+        Span::raw(" "),
+    ]);
+
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(Title::from(options).alignment(Alignment::Right));
+    if app.search.editing {
+        block = block.border_style(Style::new().red());
+        f.set_cursor(
+            chunk.x + app.search.input.cursor_position() as u16 + 1,
+            chunk.y + 1,
+        );
+    }
+    f.render_widget(app.search.input.clone().block(block), chunk);
+}
+
+/// Lines of the response body, pretty-printed and highlighted if it is JSON
+/// and `pretty` is set. A non-empty `filter` narrows a JSON body down to the
+/// values it selects.
+pub fn body_lines(
+    response: &Response,
+    pretty: bool,
+    filter: &str,
+) -> Result<Vec<Line<'static>>, String> {
+    if !filter.trim().is_empty() {
+        let Some(json) = &response.json else {
+            return Err("response body is not JSON".to_owned());
+        };
+
+        let values = filter::apply(filter, json)?;
+        let lines = if pretty {
+            values
+                .iter()
+                .flat_map(|value| json::highlight(&json::pretty_print(&value.to_string())))
+                .collect()
+        } else {
+            values
+                .iter()
+                .map(|value| Line::raw(value.to_string()))
+                .collect()
+        };
+        return Ok(lines);
+    }
+
+    Ok(match &response.pretty_body {
+        Some(pretty_body) if pretty => json::highlight(pretty_body),
+        _ => response
+            .body
+            .lines()
+            .map(|line| Line::raw(line.to_owned()))
+            .collect(),
+    })
+}
+
+fn status_line<'a>(app: &App) -> Line<'a> {
+    if let Some(pending_request) = &app.pending_request {
+        let spinner = SPINNER_FRAMES[app.tick_count % SPINNER_FRAMES.len()];
+        let elapsed = pending_request.started.elapsed().as_secs_f32();
+        return Line::raw(format!("{spinner} Sending request... {elapsed:.1}s"));
+    }
+
+    match &app.response {
+        ResponseState::None => {
+            Line::styled("No response yet", Style::default().fg(Color::DarkGray))
+        }
+        ResponseState::Received(response) => {
+            let mut spans = Vec::new();
+            // Both legs of a Digest round trip, the time is the total.
+            if let Some(challenge) = &response.challenge {
+                spans.push(Span::styled(
+                    status_text(challenge.status),
+                    Style::default().fg(status_color(challenge.status)),
+                ));
+                spans.push(Span::raw(format!(
+                    " {} ms → ",
+                    challenge.elapsed.as_millis()
+                )));
+            }
+            spans.push(Span::styled(
+                status_text(response.status),
+                Style::default()
+                    .fg(status_color(response.status))
+                    .add_modifier(Modifier::BOLD),
+            ));
+            spans.push(Span::raw(format!(
+                "  {:?}  {} ms  {}",
+                response.version,
+                response.elapsed.as_millis(),
+                format_size(response.size)
+            )));
+            let graphql_errors = graphql_errors(app).len();
+            if graphql_errors > 0 {
+                spans.push(Span::styled(
+                    format!(
+                        "  ✗ {graphql_errors} GraphQL error{}",
+                        if graphql_errors == 1 { "" } else { "s" }
+                    ),
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ));
+            }
+            Line::from(spans)
+        }
+        ResponseState::Failed(err) => Line::styled(
+            format!("✗ {}", err.title()),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ),
+        ResponseState::Cancelled => {
+            Line::styled("Request cancelled", Style::default().fg(Color::Yellow))
+        }
+    }
+}
+
+fn status_text(status: StatusCode) -> String {
+    match status.canonical_reason() {
+        Some(reason) => format!("{} {reason}", status.as_u16()),
+        None => status.as_u16().to_string(),
+    }
+}
+
+fn status_color(status: StatusCode) -> Color {
+    if status.is_success() {
+        Color::Green
+    } else if status.is_redirection() {
+        Color::Cyan
+    } else if status.is_client_error() {
+        Color::Yellow
+    } else if status.is_server_error() {
+        Color::Red
+    } else {
+        Color::White
+    }
+}
+
+pub fn format_size(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+/// The response headers, followed by those of the challenge answered before
+/// it, if any.
+fn headers_table(response: &Response) -> Table<'static> {
+    let header_rows = |headers: &HeaderMap| -> Vec<Row<'static>> {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                Row::new(vec![
+                    name.to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                ])
+            })
+            .collect()
+    };
+
+    let mut rows = header_rows(&response.headers);
+    if let Some(challenge) = &response.challenge {
+        rows.push(Row::new(vec![""]));
+        rows.push(
+            Row::new(vec![
+                status_text(challenge.status),
+                "Digest challenge, answered by the request above".to_owned(),
+            ])
+            .fg(Color::DarkGray)
+            .bold(),
+        );
+        rows.extend(
+            header_rows(&challenge.headers)
+                .into_iter()
+                .map(|row| row.fg(Color::DarkGray)),
+        );
+    }
+
+    Table::new(rows)
+        .header(Row::new(vec!["Name", "Value"]).bold())
+        .widths(&[Constraint::Percentage(35), Constraint::Percentage(65)])
+}
+
+fn cookies_table(response: &Response) -> Table<'static> {
+    let rows: Vec<Row> = response
+        .cookies()
+        .into_iter()
+        .map(|(name, value, attributes)| Row::new(vec![name, value, attributes]))
+        .collect();
+
+    Table::new(rows)
+        .header(Row::new(vec!["Name", "Value", "Attributes"]).bold())
+        .widths(&[
+            Constraint::Percentage(25),
+            Constraint::Percentage(35),
+            Constraint::Percentage(40),
+        ])
+}
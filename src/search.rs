@@ -0,0 +1,211 @@
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+use regex::{Regex, RegexBuilder};
+
+use crate::text_input::TextInput;
+
+/// A match of the search pattern inside a single response line, as byte offsets.
+#[derive(Clone, Copy)]
+pub struct SearchMatch {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+pub struct Search<'a> {
+    pub input: TextInput<'a>,
+    /// The search bar is shown below the response body.
+    pub visible: bool,
+    /// Key presses go to the search input instead of scrolling.
+    pub editing: bool,
+    pub case_sensitive: bool,
+    pub regex: bool,
+    pub matches: Vec<SearchMatch>,
+    pub current: usize,
+    /// Set when the pattern is not a valid regex.
+    pub error: Option<String>,
+}
+
+impl<'a> Search<'a> {
+    pub fn new() -> Self {
+        Self {
+            input: TextInput::new("Search"),
+            visible: false,
+            editing: false,
+            case_sensitive: false,
+            regex: false,
+            matches: Vec::new(),
+            current: 0,
+            error: None,
+        }
+    }
+
+    pub fn open(&mut self) {
+        self.visible = true;
+        self.editing = true;
+    }
+
+    pub fn close(&mut self) {
+        self.input.clear();
+        self.visible = false;
+        self.editing = false;
+        self.matches.clear();
+        self.current = 0;
+        self.error = None;
+    }
+
+    fn pattern(&self) -> Option<Result<Regex, regex::Error>> {
+        let text = self.input.text();
+        if text.is_empty() {
+            return None;
+        }
+
+        let pattern = if self.regex {
+            text.to_owned()
+        } else {
+            regex::escape(text)
+        };
+        Some(
+            RegexBuilder::new(&pattern)
+                .case_insensitive(!self.case_sensitive)
+                .build(),
+        )
+    }
+
+    /// Finds all matches in `lines` again, keeping the current match close to
+    /// `first_visible_line`.
+    pub fn update(&mut self, lines: &[Line], first_visible_line: usize) {
+        self.matches.clear();
+        self.current = 0;
+        self.error = None;
+
+        let regex = match self.pattern() {
+            Some(Ok(regex)) => regex,
+            Some(Err(err)) => {
+                self.error = Some(err.to_string());
+                return;
+            }
+            None => return,
+        };
+
+        for (line_index, line) in lines.iter().enumerate() {
+            let text = line_text(line);
+            self.matches.extend(
+                regex
+                    .find_iter(&text)
+                    .filter(|found| !found.is_empty())
+                    .map(|found| SearchMatch {
+                        line: line_index,
+                        start: found.start(),
+                        end: found.end(),
+                    }),
+            );
+        }
+
+        self.current = self
+            .matches
+            .iter()
+            .position(|found| found.line >= first_visible_line)
+            .unwrap_or(0);
+    }
+
+    pub fn next(&mut self) {
+        if !self.matches.is_empty() {
+            self.current = (self.current + 1) % self.matches.len();
+        }
+    }
+
+    pub fn previous(&mut self) {
+        if !self.matches.is_empty() {
+            self.current = (self.current + self.matches.len() - 1) % self.matches.len();
+        }
+    }
+
+    pub fn current_match(&self) -> Option<SearchMatch> {
+        self.matches.get(self.current).copied()
+    }
+
+    /// Match counter for the response block title, e.g. "3/17 matches".
+    pub fn summary(&self) -> String {
+        if let Some(err) = &self.error {
+            return format!("invalid regex: {}", err.lines().last().unwrap_or_default());
+        }
+
+        match self.matches.len() {
+            0 => "no matches".to_owned(),
+            total => format!("{}/{total} matches", self.current + 1),
+        }
+    }
+
+    /// Highlights the matches that fall on `line`, which is line `line_index`
+    /// of the searched text.
+    pub fn highlight_line(&self, line: &Line<'static>, line_index: usize) -> Line<'static> {
+        let first = self
+            .matches
+            .partition_point(|found| found.line < line_index);
+        let line_matches: Vec<(usize, SearchMatch)> = self.matches[first..]
+            .iter()
+            .take_while(|found| found.line == line_index)
+            .enumerate()
+            .map(|(offset, found)| (first + offset, *found))
+            .collect();
+        if line_matches.is_empty() {
+            return line.clone();
+        }
+
+        let match_style = Style::default().fg(Color::Black).bg(Color::Yellow);
+        let current_style = Style::default().fg(Color::Black).bg(Color::LightRed);
+
+        let mut spans = Vec::new();
+        let mut span_start = 0;
+        for span in &line.spans {
+            let content = span.content.as_ref();
+            let span_end = span_start + content.len();
+
+            // Split the span at every match boundary that falls inside it.
+            let mut position = span_start;
+            for (match_index, found) in &line_matches {
+                let start = found.start.clamp(position, span_end);
+                let end = found.end.clamp(position, span_end);
+                if start >= end {
+                    continue;
+                }
+                if position < start {
+                    spans.push(Span::styled(
+                        content[position - span_start..start - span_start].to_owned(),
+                        span.style,
+                    ));
+                }
+                let style = if *match_index == self.current {
+                    current_style
+                } else {
+                    match_style
+                };
+                spans.push(Span::styled(
+                    content[start - span_start..end - span_start].to_owned(),
+                    span.style.patch(style),
+                ));
+                position = end;
+            }
+            if position < span_end {
+                spans.push(Span::styled(
+                    content[position - span_start..].to_owned(),
+                    span.style,
+                ));
+            }
+
+            span_start = span_end;
+        }
+
+        Line::from(spans)
+    }
+}
+
+fn line_text(line: &Line) -> String {
+    line.spans
+        .iter()
+        .map(|span| span.content.as_ref())
+        .collect()
+}
@@ -0,0 +1,89 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::Line,
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::{
+    app::{is_within_block, SelectionState},
+    App,
+};
+
+pub fn render_sidebar(f: &mut Frame, app: &mut App, chunk: &Rect) {
+    let message_height = if app.collection_message.is_some() {
+        4
+    } else {
+        0
+    };
+    let blocks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(message_height)])
+        .split(*chunk);
+
+    let list_block = blocks[0];
+    let message_block = blocks[1];
+
+    if let Some(position) = app.last_mouse_down_event {
+        if is_within_block(&list_block, position) {
+            app.selected_input = SelectionState::Sidebar;
+            app.last_mouse_down_event = None;
+
+            let row = position.1.saturating_sub(list_block.y + 1) as usize;
+            let index = app.collection_state.offset() + row;
+            if index < app.collection_entries.len() {
+                app.collection_state.select(Some(index));
+            }
+        }
+    }
+
+    let items: Vec<ListItem> = app
+        .collection_entries
+        .iter()
+        .map(|entry| {
+            let indent = "  ".repeat(entry.depth);
+            if entry.is_folder {
+                let icon = if app.collapsed_folders.contains(&entry.path) {
+                    "▸"
+                } else {
+                    "▾"
+                };
+                ListItem::new(format!("{indent}{icon} {}/", entry.name())).bold()
+            } else if app.open_request.as_ref() == Some(&entry.path) {
+                ListItem::new(format!("{indent}  {}", entry.name())).fg(Color::LightGreen)
+            } else {
+                ListItem::new(format!("{indent}  {}", entry.name()))
+            }
+        })
+        .collect();
+
+    let mut block = Block::default().borders(Borders::ALL).title("Collections");
+    if let SelectionState::Sidebar = app.selected_input {
+        block = block.border_style(Style::new().red());
+    }
+
+    let list = if items.is_empty() {
+        List::new(vec![ListItem::new("CTRL + s to save").fg(Color::DarkGray)])
+    } else {
+        List::new(items)
+    }
+    .block(block)
+    .highlight_style(
+        Style::default()
+            .bg(Color::White)
+            .fg(Color::Black)
+            .add_modifier(Modifier::BOLD),
+    );
+    f.render_stateful_widget(list, list_block, &mut app.collection_state);
+
+    if let Some(message) = &app.collection_message {
+        let paragraph = Paragraph::new(Line::styled(
+            message.clone(),
+            Style::default().fg(Color::DarkGray),
+        ))
+        .wrap(Wrap { trim: true })
+        .block(Block::default().borders(Borders::ALL));
+        f.render_widget(paragraph, message_block);
+    }
+}
@@ -0,0 +1,345 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use openssl::hash::MessageDigest;
+use percent_encoding::{percent_decode_str, percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::{
+    blocking::Request,
+    header::{HeaderName, AUTHORIZATION},
+    Url,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::set_header,
+    crypto::{hex, hmac, sha256_hex},
+    request::RequestError,
+};
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// Everything but these is percent-encoded in the canonical request.
+const UNRESERVED: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SigV4Config {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: String,
+    pub region: String,
+    pub service: String,
+}
+
+/// Signs a built request with AWS Signature Version 4. Every header already on
+/// the request is signed, so this has to run after all of them are set.
+pub fn sign(
+    request: &mut Request,
+    config: &SigV4Config,
+    now: DateTime<Utc>,
+) -> Result<(), RequestError> {
+    let access_key_id = config.access_key_id.trim();
+    let region = config.region.trim();
+    let service = config.service.trim();
+    if access_key_id.is_empty() || region.is_empty() || service.is_empty() {
+        return Err(RequestError::Other(
+            "AWS SigV4 needs an access key ID, a region and a service".to_owned(),
+        ));
+    }
+
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = &amz_date[..8];
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+
+    // Streamed bodies (files and multipart forms) cannot be hashed up front.
+    // Only S3 accepts a payload it cannot check.
+    let payload_hash = match request.body() {
+        None => sha256_hex(b""),
+        Some(body) => match body.as_bytes() {
+            Some(bytes) => sha256_hex(bytes),
+            None if service == "s3" => "UNSIGNED-PAYLOAD".to_owned(),
+            None => {
+                return Err(RequestError::Other(format!(
+                    "AWS SigV4 cannot sign file or multipart bodies for '{service}', \
+                     only S3 accepts them unsigned"
+                )))
+            }
+        },
+    };
+
+    // Replaced by the signature, so it must not be signed itself.
+    request.headers_mut().remove(AUTHORIZATION);
+    set_header(request, HeaderName::from_static("x-amz-date"), &amz_date)?;
+    if !config.session_token.trim().is_empty() {
+        set_header(
+            request,
+            HeaderName::from_static("x-amz-security-token"),
+            &config.session_token,
+        )?;
+    }
+    // S3 refuses requests without the payload hash, other services do not
+    // expect it.
+    if service == "s3" {
+        set_header(
+            request,
+            HeaderName::from_static("x-amz-content-sha256"),
+            &payload_hash,
+        )?;
+    }
+
+    let (canonical_headers, signed_headers) = canonical_headers(request)?;
+    let canonical_request = [
+        request.method().as_str(),
+        &canonical_uri(request.url(), service),
+        &canonical_query(request.url()),
+        &canonical_headers,
+        &signed_headers,
+        &payload_hash,
+    ]
+    .join("\n");
+
+    let string_to_sign = [
+        ALGORITHM,
+        &amz_date,
+        &scope,
+        &sha256_hex(canonical_request.as_bytes()),
+    ]
+    .join("\n");
+
+    let sha256 = MessageDigest::sha256();
+    let secret = format!("AWS4{}", config.secret_access_key.trim());
+    let mut key = hmac(sha256, secret.as_bytes(), date.as_bytes())?;
+    for part in [region, service, "aws4_request"] {
+        key = hmac(sha256, &key, part.as_bytes())?;
+    }
+    let signature = hex(&hmac(sha256, &key, string_to_sign.as_bytes())?);
+
+    set_header(
+        request,
+        AUTHORIZATION,
+        &format!(
+            "{ALGORITHM} Credential={access_key_id}/{scope}, \
+             SignedHeaders={signed_headers}, Signature={signature}"
+        ),
+    )
+}
+
+/// Path segments are encoded once more, except for S3 which signs the path
+/// as sent.
+fn canonical_uri(url: &Url, service: &str) -> String {
+    if url.path().is_empty() {
+        return "/".to_owned();
+    }
+    url.path()
+        .split('/')
+        .map(|segment| {
+            if service == "s3" {
+                let decoded: Vec<u8> = percent_decode_str(segment).collect();
+                percent_encode(&decoded, UNRESERVED).to_string()
+            } else {
+                percent_encode(segment.as_bytes(), UNRESERVED).to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn canonical_query(url: &Url) -> String {
+    let mut pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(key, value)| {
+            (
+                percent_encode(key.as_bytes(), UNRESERVED).to_string(),
+                percent_encode(value.as_bytes(), UNRESERVED).to_string(),
+            )
+        })
+        .collect();
+    pairs.sort();
+    pairs
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Returns the canonical header block and the list of signed header names.
+/// The `Host` header is only added when the request is sent, so it is taken
+/// from the URL.
+fn canonical_headers(request: &Request) -> Result<(String, String), RequestError> {
+    let mut headers: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for (name, value) in request.headers() {
+        let value = value
+            .to_str()
+            .map_err(|_| RequestError::Other(format!("The {name} header cannot be signed")))?;
+        headers
+            .entry(name.as_str())
+            .or_default()
+            .push(value.split_whitespace().collect::<Vec<_>>().join(" "));
+    }
+
+    let url = request.url();
+    let host = match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{host}:{port}"),
+        (Some(host), None) => host.to_owned(),
+        (None, _) => String::new(),
+    };
+    headers.entry("host").or_insert_with(|| vec![host]);
+
+    let canonical = headers
+        .iter()
+        .map(|(name, values)| format!("{name}:{}\n", values.join(",")))
+        .collect();
+    let signed = headers.keys().copied().collect::<Vec<_>>().join(";");
+    Ok((canonical, signed))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use reqwest::{
+        blocking::Body,
+        header::{HeaderValue, CONTENT_TYPE},
+        Method,
+    };
+
+    use super::*;
+
+    /// The credentials, scope and time shared by AWS's SigV4 test suite.
+    fn config() -> SigV4Config {
+        SigV4Config {
+            access_key_id: "AKIDEXAMPLE".to_owned(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_owned(),
+            session_token: String::new(),
+            region: "us-east-1".to_owned(),
+            service: "service".to_owned(),
+        }
+    }
+
+    fn signed(method: Method, url: &str, body: Option<&str>, config: &SigV4Config) -> Request {
+        let mut request = Request::new(method, Url::parse(url).unwrap());
+        if let Some(body) = body {
+            request.headers_mut().insert(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/x-www-form-urlencoded"),
+            );
+            *request.body_mut() = Some(Body::from(body.to_owned()));
+        }
+        let now = Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap();
+        if let Err(err) = sign(&mut request, config, now) {
+            panic!("signing failed: {err}");
+        }
+        request
+    }
+
+    fn authorization(request: &Request) -> &str {
+        request.headers()[AUTHORIZATION].to_str().unwrap()
+    }
+
+    #[test]
+    fn get_vanilla() {
+        let request = signed(
+            Method::GET,
+            "https://example.amazonaws.com/",
+            None,
+            &config(),
+        );
+        assert_eq!(request.headers()["x-amz-date"], "20150830T123600Z");
+        assert_eq!(
+            authorization(&request),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn get_vanilla_query_order_key_case() {
+        let request = signed(
+            Method::GET,
+            "https://example.amazonaws.com/?Param2=value2&Param1=value1",
+            None,
+            &config(),
+        );
+        assert_eq!(
+            authorization(&request),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500"
+        );
+    }
+
+    #[test]
+    fn post_vanilla() {
+        let request = signed(
+            Method::POST,
+            "https://example.amazonaws.com/",
+            None,
+            &config(),
+        );
+        assert_eq!(
+            authorization(&request),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5da7c1a2acd57cee7505fc6676e4e544621c30862966e37dddb68e92efbe5d6b"
+        );
+    }
+
+    #[test]
+    fn post_x_www_form_urlencoded() {
+        let request = signed(
+            Method::POST,
+            "https://example.amazonaws.com/",
+            Some("Param1=value1"),
+            &config(),
+        );
+        assert_eq!(
+            authorization(&request),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date, \
+             Signature=ff11897932ad3f4e8b18135d722051e5ac45fc38421b1da7b9d196a0fe09473a"
+        );
+    }
+
+    #[test]
+    fn session_token_is_signed() {
+        let config = SigV4Config {
+            session_token: "token".to_owned(),
+            ..config()
+        };
+        let request = signed(Method::GET, "https://example.amazonaws.com/", None, &config);
+        assert_eq!(request.headers()["x-amz-security-token"], "token");
+        assert!(
+            authorization(&request).contains("SignedHeaders=host;x-amz-date;x-amz-security-token,")
+        );
+    }
+
+    #[test]
+    fn streamed_body_is_unsigned_for_s3_only() {
+        let streamed = |service: &str| {
+            let mut request = Request::new(
+                Method::PUT,
+                Url::parse("https://example.amazonaws.com/file").unwrap(),
+            );
+            *request.body_mut() = Some(Body::new(&b"contents"[..]));
+            let config = SigV4Config {
+                service: service.to_owned(),
+                ..config()
+            };
+            let now = Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap();
+            sign(&mut request, &config, now).map(|()| request)
+        };
+
+        match streamed("s3") {
+            Ok(request) => assert_eq!(
+                request.headers()["x-amz-content-sha256"],
+                "UNSIGNED-PAYLOAD"
+            ),
+            Err(err) => panic!("signing failed: {err}"),
+        }
+        assert!(streamed("service").is_err());
+    }
+}
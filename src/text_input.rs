@@ -1,21 +1,24 @@
 use std::cmp::{max, min};
 
 use crossterm::event::KeyCode;
+use regex::Regex;
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
-    text::Line,
+    style::Style,
+    text::{Line, Span},
     widgets::{Block, Widget},
 };
 
-use crate::app::App;
-
 #[derive(Clone)]
 pub struct TextInput<'a> {
     block: Option<Block<'a>>,
     input: String,
     cursor_position: usize,
     title: &'a str,
+    highlight: Option<(Regex, Style)>,
+    /// Characters hidden on the left, for inputs longer than their area.
+    scroll: usize,
 }
 
 impl<'a> TextInput<'a> {
@@ -25,6 +28,8 @@ impl<'a> TextInput<'a> {
             input: String::new(),
             cursor_position: 0,
             title,
+            highlight: None,
+            scroll: 0,
         }
     }
 
@@ -32,8 +37,15 @@ impl<'a> TextInput<'a> {
         &self.input
     }
 
+    /// Replaces the input and puts the cursor at its end.
+    pub fn set_text(&mut self, text: &str) {
+        self.input = text.to_owned();
+        self.cursor_position = self.input.chars().count();
+    }
+
     pub fn clear(&mut self) {
-        self.input = String::new()
+        self.input = String::new();
+        self.cursor_position = 0;
     }
 
     pub fn block(mut self, block: Block<'a>) -> TextInput<'a> {
@@ -41,6 +53,24 @@ impl<'a> TextInput<'a> {
         self
     }
 
+    /// Renders the parts of the input matching `pattern` with `style`.
+    pub fn highlight(mut self, pattern: Option<Regex>, style: Style) -> TextInput<'a> {
+        self.highlight = pattern.map(|pattern| (pattern, style));
+        self
+    }
+
+    /// Renders the input from its `offset`th character on.
+    pub fn scroll(mut self, offset: usize) -> TextInput<'a> {
+        self.scroll = offset;
+        self
+    }
+
+    /// The scroll that keeps the cursor within `width` columns, showing as
+    /// much of the text before it as fits.
+    pub fn scroll_offset(&self, width: u16) -> usize {
+        (self.cursor_position + 1).saturating_sub(max(width, 1) as usize)
+    }
+
     pub fn on_input(&mut self, keycode: KeyCode) {
         match keycode {
             KeyCode::Char(char_to_insert) => {
@@ -55,10 +85,18 @@ impl<'a> TextInput<'a> {
     }
 
     pub fn on_input_char(&mut self, char_to_insert: char) {
-        self.input.insert(self.cursor_position, char_to_insert);
+        self.input.insert(self.byte_index(), char_to_insert);
         self.cursor_position += 1;
     }
 
+    /// The cursor counts characters, `String` wants the byte offset of one.
+    fn byte_index(&self) -> usize {
+        self.input
+            .char_indices()
+            .nth(self.cursor_position)
+            .map_or(self.input.len(), |(index, _)| index)
+    }
+
     pub fn move_left(&mut self) {
         let cursor_next_position = self.cursor_position.saturating_sub(1);
         self.cursor_position = max(cursor_next_position, 0);
@@ -66,7 +104,7 @@ impl<'a> TextInput<'a> {
 
     pub fn move_right(&mut self) {
         let cursor_next_position = self.cursor_position.saturating_add(1);
-        self.cursor_position = min(cursor_next_position, self.input.len());
+        self.cursor_position = min(cursor_next_position, self.input.chars().count());
     }
 
     pub fn backspace_char(&mut self) {
@@ -83,7 +121,7 @@ impl<'a> TextInput<'a> {
     }
 
     pub fn delete_char(&mut self) {
-        if self.cursor_position < self.input.len() {
+        if self.cursor_position < self.input.chars().count() {
             let current_index = self.cursor_position;
             let from_right_to_cursor_index = current_index + 1;
 
@@ -115,7 +153,34 @@ impl<'a> Widget for TextInput<'a> {
             return;
         }
 
-        // buf.set_string(area.x, area.y, &self.input, Style::default());
-        buf.set_line(area.x + 1, area.y + 1, &Line::from(self.input), area.width);
+        let pieces = match &self.highlight {
+            Some((pattern, style)) => {
+                let mut pieces = Vec::new();
+                let mut position = 0;
+                for found in pattern.find_iter(&self.input) {
+                    pieces.push((&self.input[position..found.start()], Style::default()));
+                    pieces.push((found.as_str(), *style));
+                    position = found.end();
+                }
+                pieces.push((&self.input[position..], Style::default()));
+                pieces
+            }
+            None => vec![(self.input.as_str(), Style::default())],
+        };
+
+        // Highlighted before scrolling, so a placeholder cut off on the left
+        // keeps its style.
+        let mut hidden = self.scroll;
+        let spans: Vec<Span> = pieces
+            .into_iter()
+            .filter_map(|(text, style)| {
+                let start = text.char_indices().nth(hidden).map(|(index, _)| index);
+                hidden = hidden.saturating_sub(text.chars().count());
+                start.map(|start| Span::styled(&text[start..], style))
+            })
+            .collect();
+        let line = Line::from(spans);
+
+        buf.set_line(text_area.x, text_area.y, &line, text_area.width);
     }
 }